@@ -0,0 +1,391 @@
+use crate::pomo::daemon::{self, Request, Snapshot};
+use crate::pomo::formats::{self, Format};
+use crate::pomo::history;
+use crate::pomo::state::{session_length, Pomo, Priority, SessionMode, SessionState, Task};
+use crate::pomo::stats::{self, StatsRange};
+use crate::pomo::status;
+use crate::pomo::default_config_path;
+use crate::pomo::tasks;
+use chrono::Local;
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{ io::{self, Read}, path::PathBuf, time::Duration };
+
+#[derive(Parser)]
+#[command(name = "pomoru", version, about = "A minimalist Pomodoro TUI with a task list")]
+pub struct Cli {
+    /// Use this config file instead of the default location
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Launch the TUI with the timer already running. Lengths given here last
+    /// for this run only and are not saved to the config
+    Start {
+        /// Focus block length in minutes
+        #[arg(long, value_name = "MINS", value_parser = minutes)]
+        work: Option<u64>,
+        /// Short break length in minutes
+        #[arg(long, value_name = "MINS", value_parser = minutes)]
+        short: Option<u64>,
+        /// Long break length in minutes
+        #[arg(long, value_name = "MINS", value_parser = minutes)]
+        long: Option<u64>,
+    },
+    /// Run the timer in the background, controlled over a Unix socket
+    Daemon,
+    /// Manage the task list without opening the TUI
+    Tasks {
+        #[command(subcommand)]
+        command: TaskCommand,
+    },
+    /// Print the current session for status bars and tmux
+    Status {
+        /// Template using {mode}, {remaining}, {state}, {task} and {count}
+        #[arg(long, short, default_value = status::DEFAULT_TEMPLATE)]
+        format: String,
+        /// Print waybar-compatible JSON instead of plain text
+        #[arg(long)]
+        json: bool,
+        /// Keep running and print a new line whenever the output changes
+        #[arg(long)]
+        follow: bool,
+    },
+    /// Print focus totals from the session history
+    Stats {
+        #[arg(long, value_enum, default_value_t = RangeArg::Week)]
+        range: RangeArg,
+    },
+    /// Work with the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Report errors in the config and data files without launching
+    Check,
+}
+
+fn minutes(s: &str) -> Result<u64, String> {
+    let mins = s.parse().map_err(|e| format!("{}", e))?;
+    session_length(mins).map(|_| mins)
+}
+
+#[derive(Subcommand)]
+pub enum TaskCommand {
+    /// Append a task to the list
+    Add {
+        title: String,
+        #[arg(long, short, value_enum)]
+        priority: Option<PriorityArg>,
+        /// Expected number of pomodoros
+        #[arg(long, short, value_name = "POMODOROS")]
+        estimate: Option<u32>,
+        /// Add as a subtask of this task number
+        #[arg(long, value_name = "NUMBER")]
+        parent: Option<usize>,
+    },
+    /// Print all tasks with their numbers
+    List,
+    /// Toggle a task's completion by number
+    Done { number: usize },
+    /// Remove a task by number
+    Rm { number: usize },
+    /// Move finished tasks to the archive
+    Clear,
+    /// Append tasks from a todo.txt, Markdown or JSON file
+    Import {
+        /// Read from standard input when left out
+        file: Option<PathBuf>,
+        /// Defaults to the file's extension (.txt, .md, .json)
+        #[arg(long, short, value_enum)]
+        format: Option<FormatArg>,
+    },
+    /// Write the task list as todo.txt, Markdown or JSON. An existing Markdown
+    /// file only has the checklist items it already lists updated
+    Export {
+        /// Write to standard output when left out
+        file: Option<PathBuf>,
+        /// Defaults to the file's extension (.txt, .md, .json)
+        #[arg(long, short, value_enum)]
+        format: Option<FormatArg>,
+        /// Replace the file if it exists, Markdown included
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PriorityArg { High, Medium, Low }
+
+impl From<PriorityArg> for Priority {
+    fn from(priority: PriorityArg) -> Self {
+        match priority {
+            PriorityArg::High => Priority::High,
+            PriorityArg::Medium => Priority::Medium,
+            PriorityArg::Low => Priority::Low,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum FormatArg {
+    #[value(alias = "todo.txt", alias = "txt")]
+    Todo,
+    #[value(alias = "md")]
+    Markdown,
+    Json,
+}
+
+impl From<FormatArg> for Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Todo => Format::TodoTxt,
+            FormatArg::Markdown => Format::Markdown,
+            FormatArg::Json => Format::Json,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum RangeArg { Today, Week, Month, All }
+
+impl From<RangeArg> for StatsRange {
+    fn from(range: RangeArg) -> Self {
+        match range {
+            RangeArg::Today => StatsRange::Today,
+            RangeArg::Week => StatsRange::Week,
+            RangeArg::Month => StatsRange::Month,
+            RangeArg::All => StatsRange::AllTime,
+        }
+    }
+}
+
+pub async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    // Neither of these loads the app, which can write to the data file and the history
+    match &cli.command {
+        Some(Command::Config { command: ConfigCommand::Check }) => return check_config(&Pomo::check(cli.config)),
+        Some(Command::Status { format, json, follow }) => {
+            print_status(cli.config, format, *json, *follow).await;
+            return Ok(());
+        }
+        _ => {}
+    }
+    let mut app = Pomo::load(cli.config.clone());
+    // The TUI shows these too, but only until the first key
+    print_load_errors(&app);
+
+    match cli.command {
+        None => {
+            attach(&mut app).await;
+            app.run().await?;
+        }
+        Some(Command::Start { work, short, long }) => {
+            attach(&mut app).await;
+            let lengths = [(SessionMode::Work, work), (SessionMode::ShortBreak, short), (SessionMode::LongBreak, long)];
+            for (mode, mins) in lengths {
+                if let Some(mins) = mins { app.command(Request::SetDuration { mode, mins, temporary: true }); }
+            }
+            app.command(Request::Start);
+            app.run().await?;
+        }
+        Some(Command::Daemon) => daemon::serve(app).await?,
+        Some(Command::Tasks { command }) => run_tasks(&mut app, command).await?,
+        Some(Command::Stats { range }) => print_stats(range.into()),
+        Some(Command::Config { .. } | Command::Status { .. }) => {}
+    }
+    Ok(())
+}
+
+fn print_load_errors(app: &Pomo) {
+    for error in app.load_problems() {
+        eprintln!("pomoru: {}", error);
+    }
+    if app.config_broken && let Some(note) = app.load_error_note() {
+        eprintln!("pomoru: {}", note);
+    }
+}
+
+fn check_config(app: &Pomo) -> Result<(), Box<dyn std::error::Error>> {
+    let path = app.config_path.as_ref().ok_or("Could not find config directory")?;
+    let problems = app.load_problems();
+    if !problems.is_empty() {
+        for error in &problems {
+            eprintln!("pomoru: {}", error);
+        }
+        return Err(format!("{} problem{} found", problems.len(), if problems.len() == 1 { "" } else { "s" }).into());
+    }
+    if path.exists() {
+        println!("{} is valid", path.display());
+    } else {
+        println!("{} doesn't exist yet; the defaults are in use", path.display());
+    }
+    Ok(())
+}
+
+// Makes the TUI a client of the background daemon when one is running for the same config
+async fn attach(app: &mut Pomo) {
+    if daemon::status_for(app.config_path.as_deref()).await.is_none() { return; }
+    if let Some((requests, snapshots)) = daemon::connect().await {
+        app.remote = Some(requests);
+        app.remote_snapshots = Some(snapshots);
+    }
+}
+
+async fn run_tasks(app: &mut Pomo, command: TaskCommand) -> Result<(), Box<dyn std::error::Error>> {
+    // A running daemon owns the task list, so read from and write back through it
+    let attached = match daemon::status_for(app.config_path.as_deref()).await {
+        Some(snapshot) => { app.tasks = snapshot.tasks; app.active_task = snapshot.active_task; true }
+        None => false,
+    };
+    let before = app.tasks.clone();
+
+    match command {
+        TaskCommand::Add { title, priority, estimate, parent } => {
+            let task = Task { priority: priority.map(Into::into), estimate, ..Task::parse(&title) };
+            match parent {
+                Some(number) => {
+                    let i = task_index(app, number)?;
+                    let end = tasks::subtree_end(&app.tasks, i);
+                    app.tasks.insert(end, Task { depth: app.tasks[i].depth + 1, ..task });
+                }
+                None => app.tasks.push(task),
+            }
+        }
+        TaskCommand::List => {
+            let focus = app.focus_task().map(|t| t.id);
+            let today = Local::now().date_naive();
+            for (i, task) in app.tasks.iter().enumerate() {
+                let mark = if task.is_done { "x" } else { " " };
+                let priority = task.priority.map(|p| format!("{} ", p.marker())).unwrap_or_default();
+                let progress = task.progress().map(|p| format!("  ({})", p)).unwrap_or_default();
+                let due = match task.due {
+                    Some(d) if !task.is_done && d < today => format!("  overdue {}", tasks::date_label(d, today)),
+                    Some(d) if !task.is_done => format!("  due {}", tasks::date_label(d, today)),
+                    _ => String::new(),
+                };
+                let scheduled = task.scheduled.filter(|d| *d > today).map(|d| format!("  sched {}", tasks::date_label(d, today))).unwrap_or_default();
+                let focus = if focus == Some(task.id) { "  ← focus" } else { "" };
+                let indent = "  ".repeat(task.depth as usize);
+                println!("{:>3}. {}[{}] {}{}{}{}{}{}", i + 1, indent, mark, priority, task.title, progress, due, scheduled, focus);
+            }
+        }
+        TaskCommand::Done { number } => {
+            let i = task_index(app, number)?;
+            let done = !app.tasks[i].is_done;
+            tasks::set_done(&mut app.tasks, i, done);
+        }
+        TaskCommand::Rm { number } => {
+            let i = task_index(app, number)?;
+            let end = tasks::subtree_end(&app.tasks, i);
+            app.tasks.drain(i..end);
+        }
+        TaskCommand::Clear => app.clear_done()?,
+        TaskCommand::Import { file, format } => {
+            let imported = match file {
+                Some(path) => formats::read_file(&path, format.map(Into::into))?,
+                None => {
+                    let mut text = String::new();
+                    io::stdin().read_to_string(&mut text)?;
+                    formats::import(&text, format.ok_or("Pass --format when reading from standard input")?.into())?
+                }
+            };
+            println!("Imported {} tasks", imported.len());
+            app.tasks.extend(imported);
+        }
+        TaskCommand::Export { file, format, force } => match file {
+            Some(path) => {
+                let format = format.map(Into::into);
+                if !force && formats::replaces_file(&path, format) {
+                    return Err(format!("{} already exists; pass --force to replace it", path.display()).into());
+                }
+                let written = formats::write_file(&path, &app.tasks, format, force)?;
+                println!("Exported {} tasks", written);
+            }
+            None => print!("{}", formats::export(&app.tasks, format.ok_or("Pass --format when writing to standard output")?.into())?),
+        },
+    }
+
+    app.settle_tasks();
+    if app.tasks != before {
+        if attached {
+            daemon::request(&Request::SetTasks { tasks: app.tasks.clone(), base: Some(before) }).await?;
+        } else {
+            app.save()?;
+        }
+    }
+    Ok(())
+}
+
+fn task_index(app: &Pomo, number: usize) -> Result<usize, String> {
+    if number == 0 || number > app.tasks.len() {
+        return Err(format!("No task number {} (have {})", number, app.tasks.len()));
+    }
+    Ok(number - 1)
+}
+
+async fn print_status(config: Option<PathBuf>, template: &str, json: bool, follow: bool) {
+    let render = |s: &Snapshot| if json { status::render_json(template, s) } else { status::render(template, s) };
+    let mut last = None;
+    // Without a daemon the saved session (and its deadline) is the source of truth.
+    // The config is read once, the data file again whenever it changes.
+    let mut saved: Option<(Pomo, Option<SessionState>)> = None;
+    let config_path = config.clone().or_else(default_config_path);
+
+    loop {
+        let snapshot = match daemon::status_for(config_path.as_deref()).await {
+            Some(snapshot) => snapshot,
+            None => {
+                let (app, session) = saved.get_or_insert_with(|| (Pomo::load_preferences(config.clone()), None));
+                if let Some(data) = app.read_data_if_changed() {
+                    app.tasks = data.tasks;
+                    app.active_task = data.active_task;
+                    *session = data.session;
+                }
+                app.saved_snapshot(session.as_ref())
+            }
+        };
+
+        let line = render(&snapshot);
+        if last.as_ref() != Some(&line) {
+            println!("{}", line);
+            last = Some(line);
+        }
+
+        if !follow { break; }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+fn print_stats(range: StatsRange) {
+    let summary = stats::summarize(&history::load(), range, Local::now().date_naive());
+    let compliance = summary.break_compliance()
+        .map(|pct| format!("{}%", pct))
+        .unwrap_or_else(|| "—".to_string());
+
+    println!("{}", range.label());
+    println!("  Focus         {}", stats::format_hours(summary.focus_secs));
+    println!("  Pomodoros     {}", summary.pomodoros);
+    println!("  Breaks taken  {}", compliance);
+    println!();
+    if summary.projects.iter().any(|p| p.project.is_some()) {
+        for project in &summary.projects {
+            let name = project.project.as_deref().map_or("(none)".to_string(), |p| format!("+{}", p));
+            println!("  {:<12}  {:>8}  {} pomodoros", name, stats::format_hours(project.focus_secs), project.pomodoros);
+        }
+        println!();
+    }
+    for day in summary.days.iter().filter(|d| d.focus_secs > 0 || d.pomodoros > 0) {
+        println!(
+            "  {}  {:>8}  {} pomodoros",
+            day.date.format("%a %Y-%m-%d"), stats::format_hours(day.focus_secs), day.pomodoros
+        );
+    }
+}
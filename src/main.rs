@@ -1,5 +1,6 @@
+mod cli;
 mod pomo;
-use pomo::state::Pomo;
+use clap::Parser;
 use std::panic;
 use crossterm::terminal::{disable_raw_mode, LeaveAlternateScreen};
 use crossterm::execute;
@@ -18,9 +19,10 @@ fn setup_panic_hook() {
 }
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() {
     setup_panic_hook();
-    let mut app = Pomo::load();
-    app.run().await?;
-    Ok(())
+    if let Err(e) = cli::run(cli::Cli::parse()).await {
+        eprintln!("pomoru: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,36 @@
+use crate::pomo::state::Task;
+use directories::ProjectDirs;
+use std::{ fs::{self, OpenOptions}, io::Write, path::PathBuf };
+
+// Finished tasks moved out of the list, one JSON line per task in the order they
+// were archived. A subtask follows its parent, as in the list.
+
+pub fn archive_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "pomoru").map(|dirs| dirs.data_dir().join("archive.jsonl"))
+}
+
+pub fn append(tasks: &[Task]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = archive_path().ok_or("Could not find data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut lines = String::new();
+    for task in tasks {
+        lines.push_str(&serde_json::to_string(task)?);
+        lines.push('\n');
+    }
+
+    // One write for the whole batch keeps a subtree together
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())?;
+    Ok(())
+}
+
+pub fn load() -> Vec<Task> {
+    let Some(path) = archive_path() else { return Vec::new() };
+    let Ok(content) = fs::read_to_string(path) else { return Vec::new() };
+    content.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
@@ -0,0 +1,313 @@
+use crate::pomo::state::{session_length, Pomo, SessionMode, SessionState, Task};
+use chrono::Local;
+use crate::pomo::tasks;
+use directories::ProjectDirs;
+use serde::{ Serialize, Deserialize };
+use std::{ fs, path::{self, Path, PathBuf}, sync::{Arc, Mutex}, time::Duration };
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::{mpsc, watch},
+};
+
+// One JSON object per line in each direction. Every request is answered with a
+// status line; after `subscribe` the daemon also pushes a status line on every change.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(tag = "cmd", rename_all = "kebab-case")]
+pub enum Request {
+    Start,
+    Pause,
+    Toggle,
+    Skip,
+    Reset,
+    Status,
+    Subscribe,
+    AddTask { title: String },
+    // `base` is the list the client started editing from, so changes the daemon
+    // made in the meantime are merged in rather than replaced
+    SetTasks {
+        tasks: Vec<Task>,
+        #[serde(default)]
+        base: Option<Vec<Task>>,
+    },
+    SetActiveTask { id: Option<u64> },
+    SetMode { mode: SessionMode },
+    // `temporary` lengths hold until the daemon stops and aren't saved to the config
+    SetDuration {
+        mode: SessionMode,
+        mins: u64,
+        #[serde(default)]
+        temporary: bool,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    Status(Snapshot),
+    Error { message: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Snapshot {
+    pub mode: SessionMode,
+    pub remaining_secs: u64,
+    pub total_secs: u64,
+    pub is_running: bool,
+    pub break_count: u32,
+    pub task: Option<String>,
+    #[serde(default)]
+    pub active_task: Option<u64>,
+    pub tasks: Vec<Task>,
+    // The config the daemon was started with; clients using another one don't attach
+    #[serde(default)]
+    pub config_path: Option<PathBuf>,
+}
+
+pub fn socket_path() -> Option<PathBuf> {
+    let dirs = ProjectDirs::from("", "", "pomoru")?;
+    let dir = dirs.runtime_dir().unwrap_or(dirs.data_dir());
+    Some(dir.join("pomoru.sock"))
+}
+
+impl Pomo {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            mode: self.mode,
+            remaining_secs: self.time_remaining.as_secs(),
+            total_secs: self.total_duration.as_secs(),
+            is_running: self.is_running,
+            break_count: self.break_count,
+            task: self.focus_task().map(|t| t.title.clone()),
+            active_task: self.active_task,
+            tasks: self.tasks.clone(),
+            config_path: self.config_path.as_deref().and_then(|p| path::absolute(p).ok()),
+        }
+    }
+
+    // What `snapshot` would say after restoring a saved session, worked out from
+    // its deadline instead. A block that ran out shows as ended rather than
+    // moving on, since that would log it to the history.
+    pub fn saved_snapshot(&self, session: Option<&SessionState>) -> Snapshot {
+        let mut snapshot = self.snapshot();
+        if let Some(session) = session {
+            let remaining = match session.deadline {
+                Some(deadline) if session.is_running => (deadline - Local::now()).to_std().unwrap_or_default(),
+                _ => Duration::from_secs(session.remaining_secs),
+            };
+            snapshot.mode = session.mode;
+            snapshot.remaining_secs = remaining.as_secs();
+            snapshot.total_secs = self.mode_duration(session.mode).as_secs();
+            snapshot.is_running = session.is_running && !remaining.is_zero();
+            snapshot.break_count = session.break_count;
+        }
+        snapshot
+    }
+
+    // Mirrors the daemon's state when the TUI runs as a client; the clock itself is never ticked locally
+    pub fn apply_snapshot(&mut self, snapshot: Snapshot) {
+        self.mode = snapshot.mode;
+        self.time_remaining = Duration::from_secs(snapshot.remaining_secs);
+        self.total_duration = Duration::from_secs(snapshot.total_secs);
+        self.is_running = snapshot.is_running;
+        self.break_count = snapshot.break_count;
+        self.tasks = snapshot.tasks;
+        self.active_task = snapshot.active_task;
+        let rows = self.task_rows().len();
+        if self.task_state.selected().is_some_and(|i| i >= rows) {
+            self.task_state.select(rows.checked_sub(1));
+        }
+    }
+
+    pub fn apply_request(&mut self, request: Request) -> Result<(), String> {
+        match request {
+            Request::Start => self.resume(),
+            Request::Pause => self.pause(),
+            Request::Toggle => self.toggle_timer(),
+            Request::Skip => self.skip_session(),
+            Request::Reset => self.reset_session(),
+            Request::AddTask { title } => self.tasks.push(Task::parse(&title)),
+            Request::SetTasks { tasks, base: Some(base) } => self.tasks = tasks::merge(&base, &self.tasks, &tasks),
+            Request::SetTasks { tasks, base: None } => self.tasks = tasks,
+            Request::SetActiveTask { id } => self.set_active_task(id),
+            Request::SetMode { mode } => self.set_mode(mode),
+            Request::SetDuration { mode, mins, temporary: false } => self.set_duration(mode, session_length(mins)?),
+            Request::SetDuration { mode, mins, temporary: true } => self.set_run_duration(mode, session_length(mins)?),
+            Request::Status | Request::Subscribe => {}
+        }
+        self.settle_tasks();
+        self.tick();
+        Ok(())
+    }
+
+    // Forwards to the daemon when attached to one, otherwise applies locally
+    pub fn command(&mut self, request: Request) {
+        match &self.remote {
+            Some(remote) => { let _ = remote.send(request); }
+            // Out-of-range lengths from the TUI are ignored like unparsable ones
+            None => { let _ = self.apply_request(request); }
+        }
+    }
+}
+
+// Sends a single request to a running daemon and returns its status reply
+pub async fn request(request: &Request) -> Result<Snapshot, Box<dyn std::error::Error>> {
+    let path = socket_path().ok_or("Could not find runtime directory")?;
+    let stream = UnixStream::connect(path).await?;
+    let (read, mut write) = stream.into_split();
+
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    write.write_all(line.as_bytes()).await?;
+
+    let reply = BufReader::new(read).lines().next_line().await?.ok_or("Daemon closed the connection")?;
+    match serde_json::from_str(&reply)? {
+        Response::Status(snapshot) => Ok(snapshot),
+        Response::Error { message } => Err(message.into()),
+    }
+}
+
+// Opens a subscribed connection for the TUI: requests go out through the sender,
+// status updates come back on the receiver until the daemon goes away
+// Status from a running daemon, if it serves `config_path`
+pub async fn status_for(config_path: Option<&Path>) -> Option<Snapshot> {
+    let ours = config_path.and_then(|p| path::absolute(p).ok());
+    request(&Request::Status).await.ok().filter(|s| s.config_path.is_some() && s.config_path == ours)
+}
+
+pub async fn connect() -> Option<(mpsc::UnboundedSender<Request>, mpsc::UnboundedReceiver<Snapshot>)> {
+    let stream = UnixStream::connect(socket_path()?).await.ok()?;
+    let (read, mut write) = stream.into_split();
+    let (request_tx, mut request_rx) = mpsc::unbounded_channel::<Request>();
+    let (snapshot_tx, snapshot_rx) = mpsc::unbounded_channel();
+
+    let _ = request_tx.send(Request::Subscribe);
+    tokio::spawn(async move {
+        while let Some(request) = request_rx.recv().await {
+            let Ok(mut line) = serde_json::to_string(&request) else { continue };
+            line.push('\n');
+            if write.write_all(line.as_bytes()).await.is_err() { break; }
+        }
+    });
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            if let Ok(Response::Status(snapshot)) = serde_json::from_str(&line)
+                && snapshot_tx.send(snapshot).is_err() {
+                break;
+            }
+        }
+    });
+
+    Some((request_tx, snapshot_rx))
+}
+
+pub async fn serve(app: Pomo) -> Result<(), Box<dyn std::error::Error>> {
+    let path = socket_path().ok_or("Could not find runtime directory")?;
+    if UnixStream::connect(&path).await.is_ok() {
+        return Err(format!("A daemon is already listening on {}", path.display()).into());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let _ = fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+
+    let (snapshot_tx, _) = watch::channel(app.snapshot());
+    let app = Arc::new(Mutex::new(app));
+
+    let mut tick = tokio::time::interval(Duration::from_millis(250));
+    tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_session_key = app.lock().unwrap().session_key();
+    // Already printed at startup; later ones are printed as config edits are picked up
+    app.lock().unwrap().clear_load_problems();
+
+    let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                let mut app = app.lock().unwrap();
+                app.reload_changed();
+                for error in app.load_problems() {
+                    eprintln!("pomoru: {}", error);
+                }
+                app.clear_load_problems();
+                app.tick();
+                let rolled_over = app.roll_over();
+                // Nobody is around to answer the suspend prompt, so keep counting
+                app.input_mode = crate::pomo::state::InputMode::Normal;
+                app.suspend_gap = Duration::ZERO;
+
+                let session_key = app.session_key();
+                if session_key != last_session_key || rolled_over || app.save_due() {
+                    last_session_key = session_key;
+                    let _ = app.save();
+                }
+                snapshot_tx.send_if_modified(|s| replace_if_changed(s, app.snapshot()));
+            }
+
+            accepted = listener.accept() => {
+                if let Ok((stream, _)) = accepted {
+                    tokio::spawn(handle_client(stream, app.clone(), snapshot_tx.clone()));
+                }
+            }
+
+            _ = tokio::signal::ctrl_c() => break,
+            _ = terminate.recv() => break,
+        }
+    }
+
+    let _ = app.lock().unwrap().save();
+    let _ = fs::remove_file(&path);
+    Ok(())
+}
+
+async fn handle_client(stream: UnixStream, app: Arc<Mutex<Pomo>>, snapshot_tx: watch::Sender<Snapshot>) {
+    let (read, mut write) = stream.into_split();
+    let mut lines = BufReader::new(read).lines();
+    let mut updates = snapshot_tx.subscribe();
+    let mut subscribed = false;
+
+    loop {
+        let response = tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else { break };
+                match serde_json::from_str::<Request>(&line) {
+                    Ok(request) => {
+                        subscribed |= matches!(request, Request::Subscribe);
+                        let mutates = !matches!(request, Request::Status | Request::Subscribe);
+
+                        let mut app = app.lock().unwrap();
+                        match app.apply_request(request) {
+                            Ok(()) => {
+                                if mutates { let _ = app.save(); }
+                                let snapshot = app.snapshot();
+                                snapshot_tx.send_if_modified(|s| replace_if_changed(s, snapshot.clone()));
+                                updates.mark_unchanged();
+                                Response::Status(snapshot)
+                            }
+                            Err(message) => Response::Error { message },
+                        }
+                    }
+                    Err(e) => Response::Error { message: e.to_string() },
+                }
+            }
+
+            changed = updates.changed(), if subscribed => {
+                if changed.is_err() { break; }
+                Response::Status(updates.borrow_and_update().clone())
+            }
+        };
+
+        let Ok(mut line) = serde_json::to_string(&response) else { break };
+        line.push('\n');
+        if write.write_all(line.as_bytes()).await.is_err() { break; }
+    }
+}
+
+fn replace_if_changed(current: &mut Snapshot, next: Snapshot) -> bool {
+    if *current == next { return false; }
+    *current = next;
+    true
+}
@@ -0,0 +1,204 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// Single-line text field for the input modal. The cursor is a byte offset that
+// always sits on a grapheme boundary, so an accented letter, a CJK character or
+// an emoji sequence moves and deletes as one.
+#[derive(Default, Clone)]
+pub struct LineEditor {
+    text: String,
+    cursor: usize
+}
+
+impl LineEditor {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // Replaces the contents and puts the cursor at the end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.set(String::new());
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn move_left(&mut self) {
+        self.cursor = self.prev_boundary();
+    }
+
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    pub fn move_word_left(&mut self) {
+        self.cursor = self.prev_word();
+    }
+
+    pub fn move_word_right(&mut self) {
+        self.cursor = self.next_word();
+    }
+
+    pub fn delete_backward(&mut self) {
+        let start = self.prev_boundary();
+        self.delete_range(start, self.cursor);
+    }
+
+    pub fn delete_forward(&mut self) {
+        let end = self.next_boundary();
+        self.delete_range(self.cursor, end);
+    }
+
+    pub fn delete_word_backward(&mut self) {
+        let start = self.prev_word();
+        self.delete_range(start, self.cursor);
+    }
+
+    pub fn delete_to_start(&mut self) {
+        self.delete_range(0, self.cursor);
+    }
+
+    pub fn delete_to_end(&mut self) {
+        self.delete_range(self.cursor, self.text.len());
+    }
+
+    // The part of the text that fits in `width` columns with the cursor in view,
+    // and the cursor's column within it. Scrolls just far enough to keep the
+    // cursor on screen, leaving a column for it at the end of the line.
+    pub fn view(&self, width: usize) -> (String, usize) {
+        let cursor_col = self.text[..self.cursor].width();
+        let skip = (cursor_col + 1).saturating_sub(width);
+
+        let mut col = 0;
+        let mut start_col = None;
+        let mut visible = String::new();
+        for g in self.text.graphemes(true) {
+            let w = g.width();
+            // Wide characters are skipped whole rather than cut in half
+            if start_col.is_none() && col >= skip {
+                start_col = Some(col);
+            }
+            if let Some(start) = start_col {
+                if col + w - start > width { break; }
+                visible.push_str(g);
+            }
+            col += w;
+        }
+        let start = start_col.unwrap_or(col);
+        (visible, cursor_col.saturating_sub(start))
+    }
+
+    fn delete_range(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.cursor = start;
+    }
+
+    fn prev_boundary(&self) -> usize {
+        self.text[..self.cursor].grapheme_indices(true).next_back().map_or(0, |(i, _)| i)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.text[self.cursor..].graphemes(true).next().map_or(self.cursor, |g| self.cursor + g.len())
+    }
+
+    // Start of the word before the cursor, skipping any spaces in between
+    fn prev_word(&self) -> usize {
+        let before = self.text[..self.cursor].trim_end();
+        before.char_indices().rev().find(|(_, c)| c.is_whitespace()).map_or(0, |(i, c)| i + c.len_utf8())
+    }
+
+    // End of the word after the cursor, skipping any spaces in between
+    fn next_word(&self) -> usize {
+        let after = &self.text[self.cursor..];
+        let skipped = after.len() - after.trim_start().len();
+        let rest = &after[skipped..];
+        self.cursor + skipped + rest.find(char::is_whitespace).unwrap_or(rest.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str, cursor: usize) -> LineEditor {
+        LineEditor { text: text.to_string(), cursor }
+    }
+
+    #[test]
+    fn wide_character_at_the_scroll_edge_is_skipped_whole() {
+        // 中 takes columns 2 and 3; scrolling to column 3 would cut it in half
+        let end = editor("ab中cd", "ab中cd".len());
+        assert_eq!(end.view(4), ("cd".to_string(), 2));
+        let after_wide = editor("ab中cd", "ab中".len());
+        assert_eq!(after_wide.view(3), ("中c".to_string(), 2));
+    }
+
+    #[test]
+    fn emoji_sequence_moves_and_deletes_as_one() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let mut e = editor(&format!("a{}", family), 1 + family.len());
+        e.move_left();
+        assert_eq!(e.cursor, 1);
+        e.move_end();
+        e.delete_backward();
+        assert_eq!((e.text(), e.cursor), ("a", 1));
+    }
+
+    #[test]
+    fn combining_accent_stays_with_its_letter() {
+        let mut e = editor("e\u{301}x", 4);
+        e.move_left();
+        assert_eq!(e.cursor, 3);
+        e.move_left();
+        assert_eq!(e.cursor, 0);
+        e.move_right();
+        e.delete_backward();
+        assert_eq!((e.text(), e.cursor), ("x", 0));
+    }
+
+    #[test]
+    fn word_motions_skip_runs_of_spaces() {
+        let text = "foo   bar  baz";
+        let mut e = editor(text, text.len());
+        e.move_word_left();
+        assert_eq!(e.cursor, 11);
+        e.move_word_left();
+        assert_eq!(e.cursor, 6);
+
+        let mut e = editor(text, 3);
+        e.move_word_right();
+        assert_eq!(e.cursor, 9);
+        e.move_word_right();
+        assert_eq!(e.cursor, text.len());
+
+        let mut e = editor(text, 9);
+        e.delete_word_backward();
+        assert_eq!((e.text(), e.cursor), ("foo     baz", 6));
+    }
+
+    #[test]
+    fn delete_to_either_end_from_the_middle() {
+        let mut e = editor("hello world", 5);
+        e.delete_to_start();
+        assert_eq!((e.text(), e.cursor), (" world", 0));
+
+        let mut e = editor("hello world", 5);
+        e.delete_to_end();
+        assert_eq!((e.text(), e.cursor), ("hello", 5));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::pomo::state::{Priority, Task};
+use crate::pomo::storage;
+use crate::pomo::tasks;
+use chrono::{Local, NaiveDate, TimeZone};
+use directories::BaseDirs;
+use std::{ fs, path::{Path, PathBuf} };
+
+// Task list interchange. The text formats put whatever todo.txt or Markdown has
+// no syntax for into `key:value` words, so a list survives the round trip.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format { TodoTxt, Markdown, Json }
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "txt" => Some(Format::TodoTxt),
+            "md" | "markdown" => Some(Format::Markdown),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn export(tasks: &[Task], format: Format) -> Result<String, Box<dyn std::error::Error>> {
+    let mut out = match format {
+        Format::Json => serde_json::to_string_pretty(tasks)?,
+        Format::TodoTxt => tasks.iter().map(todo_txt_line).collect::<Vec<_>>().join("\n"),
+        Format::Markdown => tasks.iter().map(markdown_line).collect::<Vec<_>>().join("\n"),
+    };
+    out.push('\n');
+    Ok(out)
+}
+
+// Imported tasks come without ids, so they get fresh ones when added to a list
+pub fn import(text: &str, format: Format) -> Result<Vec<Task>, Box<dyn std::error::Error>> {
+    let mut tasks = match format {
+        Format::Json => serde_json::from_str::<Vec<Task>>(text)?,
+        Format::TodoTxt => text.lines().map(parse_todo_txt).filter(|t| !t.title.is_empty()).collect(),
+        Format::Markdown => parse_markdown(text),
+    };
+    for task in &mut tasks {
+        task.id = 0;
+    }
+    // Starts a new outline, whatever list it ends up appended to
+    if let Some(first) = tasks.first_mut() {
+        first.depth = 0;
+    }
+    tasks::normalize_depths(&mut tasks);
+    Ok(tasks)
+}
+
+// The format comes from the extension unless given
+pub fn read_file(path: &Path, format: Option<Format>) -> Result<Vec<Task>, Box<dyn std::error::Error>> {
+    let format = format.or_else(|| Format::from_path(path)).ok_or_else(|| unknown_format(path))?;
+    import(&fs::read_to_string(path)?, format)
+}
+
+// An existing Markdown file, like a project's README, only has its checklist
+// items updated unless `replace` is set. Returns how many tasks were written.
+pub fn write_file(path: &Path, tasks: &[Task], format: Option<Format>, replace: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    let format = format.or_else(|| Format::from_path(path)).ok_or_else(|| unknown_format(path))?;
+    if format == Format::Markdown && !replace && path.exists() {
+        let (text, updated) = update_markdown(&fs::read_to_string(path)?, tasks);
+        storage::write_atomic(path, &text)?;
+        return Ok(updated);
+    }
+    storage::write_atomic(path, &export(tasks, format)?)?;
+    Ok(tasks.len())
+}
+
+// Whether `write_file` would replace a file that is already there
+pub fn replaces_file(path: &Path, format: Option<Format>) -> bool {
+    path.exists() && format.or_else(|| Format::from_path(path)) != Some(Format::Markdown)
+}
+
+// Paths typed into the TUI don't go through a shell
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+fn unknown_format(path: &Path) -> String {
+    format!("Can't tell the format of {} from its extension (.txt, .md or .json)", path.display())
+}
+
+// `x 2026-10-18 (A) Title +project @tag due:2026-11-03 t:2026-10-30 est:4 pomo:2 collapsed:1 depth:1`
+fn todo_txt_line(task: &Task) -> String {
+    let mut words = Vec::new();
+    if task.is_done {
+        words.push("x".to_string());
+        if let Some(at) = task.completed_at {
+            words.push(at.date_naive().to_string());
+        }
+    }
+    // Completed tasks keep their priority as a tag, the usual todo.txt convention
+    match task.priority.map(priority_letter) {
+        Some(letter) if task.is_done => words.push(format!("pri:{}", letter)),
+        Some(letter) => words.push(format!("({})", letter)),
+        None => {}
+    }
+    words.push(task.title.clone());
+    words.extend(task.project.iter().map(|p| format!("+{}", p)));
+    words.extend(task.tags.iter().map(|t| format!("@{}", t)));
+    words.extend(task.due.map(|d| format!("due:{}", d)));
+    words.extend(task.scheduled.map(|d| format!("t:{}", d)));
+    words.extend(extra_words(task));
+    if task.depth > 0 {
+        words.push(format!("depth:{}", task.depth));
+    }
+    words.join(" ")
+}
+
+fn parse_todo_txt(line: &str) -> Task {
+    let mut words = line.split_whitespace().peekable();
+    let mut task = Task::new(String::new());
+    let mut completed = None;
+
+    if words.next_if_eq(&"x").is_some() {
+        task.is_done = true;
+        completed = words.next_if(|w| parse_iso(w).is_some()).and_then(parse_iso);
+    }
+    // The priority goes before the creation date, which pomoru doesn't keep,
+    // though some tools write it after the dates of a finished task
+    for _ in 0..2 {
+        if let Some(word) = words.next_if(|w| w.len() == 3 && w.starts_with('(') && w.ends_with(')')) {
+            task.priority = word.chars().nth(1).and_then(priority_from_letter);
+        }
+        words.next_if(|w| parse_iso(w).is_some());
+    }
+
+    let mut rest = Vec::new();
+    for word in words {
+        if let Some(letter) = word.strip_prefix("pri:") {
+            task.priority = letter.chars().next().and_then(priority_from_letter);
+        } else if let Some(depth) = word.strip_prefix("depth:").and_then(|d| d.parse().ok()) {
+            task.depth = depth;
+        } else if let Some(date) = word.strip_prefix("t:") {
+            rest.push(format!("sched:{}", date));
+        } else if let Some(tag) = word.strip_prefix('@').filter(|t| !t.is_empty()) {
+            rest.push(format!("#{}", tag));
+        } else if !take_extra_word(&mut task, word) {
+            rest.push(word.to_string());
+        }
+    }
+    task.set_entry(&rest.join(" "));
+    if let Some(date) = completed {
+        task.completed_at = start_of_day(date);
+    }
+    task
+}
+
+// `  - [x] !!! Title +project #tag due:2026-11-03 est:4 pomo:2 collapsed:1 done:2026-10-18`
+fn markdown_line(task: &Task) -> String {
+    format!("{}- {}", "  ".repeat(task.depth as usize), markdown_item(task))
+}
+
+// The part of a checklist line after the bullet
+fn markdown_item(task: &Task) -> String {
+    let mut words = vec![format!("[{}]", if task.is_done { "x" } else { " " })];
+    words.extend(task.priority.map(|p| p.marker().to_string()));
+    words.push(task.entry());
+    words.extend(extra_words(task));
+    if task.is_done && let Some(at) = task.completed_at {
+        words.push(format!("done:{}", at.date_naive()));
+    }
+    words.join(" ")
+}
+
+// Checklist items anywhere in the text; everything else, like headings and
+// prose in a README, is skipped. Nesting follows the indentation.
+fn parse_markdown(text: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut indents: Vec<usize> = Vec::new();
+    for line in text.lines() {
+        let Some((_, done, item)) = checklist_item(line) else { continue };
+        let indent = line.len() - line.trim_start().len();
+        while indents.last().is_some_and(|i| *i >= indent) {
+            indents.pop();
+        }
+        let depth = indents.len() as u32;
+        indents.push(indent);
+
+        let task = Task { depth, ..parse_markdown_item(done, item) };
+        if !task.title.is_empty() {
+            tasks.push(task);
+        }
+    }
+    tasks
+}
+
+// Rewrites the checklist items that match a task by title with that task's
+// state. Everything else in the file, including items pomoru doesn't have,
+// stays exactly as it was. Returns the new text and how many items matched.
+fn update_markdown(text: &str, tasks: &[Task]) -> (String, usize) {
+    let mut used = vec![false; tasks.len()];
+    let mut out = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let matched = checklist_item(body).and_then(|(bullet, done, item)| {
+            let title = parse_markdown_item(done, item).title;
+            let i = (0..tasks.len()).find(|&i| !used[i] && !title.is_empty() && tasks[i].title == title)?;
+            used[i] = true;
+            Some(format!("{}{}", bullet, markdown_item(&tasks[i])))
+        });
+        match matched {
+            Some(updated) => {
+                out.push_str(&updated);
+                out.push_str(&line[body.len()..]);
+            }
+            None => out.push_str(line),
+        }
+    }
+    (out, used.iter().filter(|u| **u).count())
+}
+
+// Splits `  - [x] rest` into the indented bullet, whether it's ticked, and the rest
+fn checklist_item(line: &str) -> Option<(&str, bool, &str)> {
+    let trimmed = line.trim_start();
+    let item = trimmed.strip_prefix("- ").or(trimmed.strip_prefix("* "))?;
+    let done = match item.get(..3) {
+        Some("[ ]") => false,
+        Some("[x]" | "[X]") => true,
+        _ => return None,
+    };
+    Some((&line[..line.len() - item.len()], done, &item[3..]))
+}
+
+fn parse_markdown_item(done: bool, item: &str) -> Task {
+    let mut task = Task::new(String::new());
+    task.is_done = done;
+    let mut words = item.split_whitespace().peekable();
+    task.priority = match words.peek() {
+        Some(&"!!!") => Some(Priority::High),
+        Some(&"!!") => Some(Priority::Medium),
+        Some(&"!") => Some(Priority::Low),
+        _ => None,
+    };
+    if task.priority.is_some() {
+        words.next();
+    }
+    let rest = words.filter(|w| !take_extra_word(&mut task, w)).collect::<Vec<_>>();
+    task.set_entry(&rest.join(" "));
+    task
+}
+
+// Metadata that neither format has syntax for
+fn extra_words(task: &Task) -> Vec<String> {
+    let mut words = Vec::new();
+    words.extend(task.estimate.map(|e| format!("est:{}", e)));
+    if task.pomodoros > 0 {
+        words.push(format!("pomo:{}", task.pomodoros));
+    }
+    if task.collapsed {
+        words.push("collapsed:1".to_string());
+    }
+    words
+}
+
+fn take_extra_word(task: &mut Task, word: &str) -> bool {
+    if let Some(estimate) = word.strip_prefix("est:").and_then(|e| e.parse().ok()) {
+        task.estimate = Some(estimate);
+    } else if let Some(pomodoros) = word.strip_prefix("pomo:").and_then(|p| p.parse().ok()) {
+        task.pomodoros = pomodoros;
+    } else if word == "collapsed:1" {
+        task.collapsed = true;
+    } else if let Some(date) = word.strip_prefix("done:").and_then(parse_iso) {
+        task.completed_at = start_of_day(date);
+    } else {
+        return false;
+    }
+    true
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+// todo.txt goes down to Z; everything below C counts as low
+fn priority_from_letter(letter: char) -> Option<Priority> {
+    match letter {
+        'A' => Some(Priority::High),
+        'B' => Some(Priority::Medium),
+        'C'..='Z' => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_iso(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+fn start_of_day(date: NaiveDate) -> Option<chrono::DateTime<Local>> {
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Everything both text formats carry, with a finished parent over an
+    // unfinished subtask and a nested, folded subtree
+    fn sample() -> Vec<Task> {
+        let mut parent = Task::parse("Write report +work #writing due:2026-11-03 sched:2026-10-30");
+        parent.priority = Some(Priority::High);
+        parent.estimate = Some(4);
+        parent.pomodoros = 2;
+        parent.is_done = true;
+        parent.completed_at = start_of_day(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap());
+
+        let mut child = Task::parse("Outline #draft");
+        child.depth = 1;
+        child.priority = Some(Priority::Low);
+        child.collapsed = true;
+
+        let mut grandchild = Task::parse("Collect sources");
+        grandchild.depth = 2;
+        grandchild.estimate = Some(1);
+
+        let mut other = Task::parse("Invoice +acme");
+        other.priority = Some(Priority::Medium);
+
+        vec![parent, child, grandchild, other]
+    }
+
+    fn round_trip(format: Format) {
+        let tasks = sample();
+        let imported = import(&export(&tasks, format).unwrap(), format).unwrap();
+        assert!(imported == tasks, "{}", export(&imported, format).unwrap());
+    }
+
+    #[test]
+    fn todo_txt_round_trip() {
+        round_trip(Format::TodoTxt);
+    }
+
+    #[test]
+    fn markdown_round_trip() {
+        round_trip(Format::Markdown);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(Format::Json);
+    }
+
+    #[test]
+    fn todo_txt_priority_after_completion_date() {
+        let task = parse_todo_txt("x 2026-10-18 2026-10-01 (B) Call back +acme");
+        assert!(task.is_done);
+        assert!(task.priority == Some(Priority::Medium));
+        assert_eq!(task.entry(), "Call back +acme");
+    }
+
+    #[test]
+    fn markdown_update_keeps_the_rest_of_the_file() {
+        let readme = "# Project\r\n\nSome prose.\n\n* [ ] Ship it\n  - [ ] Write docs\n- [ ] Not in pomoru\n\n## License\nMIT";
+        let mut tasks = import(readme, Format::Markdown).unwrap();
+        tasks.retain(|t| t.title != "Not in pomoru");
+        tasks[1].is_done = true;
+        tasks[0].priority = Some(Priority::High);
+        tasks.push(Task::parse("Unrelated"));
+
+        let (text, updated) = update_markdown(readme, &tasks);
+        assert_eq!(updated, 2);
+        assert_eq!(text, "# Project\r\n\nSome prose.\n\n* [ ] !!! Ship it\n  - [x] Write docs\n- [ ] Not in pomoru\n\n## License\nMIT");
+    }
+}
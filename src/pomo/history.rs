@@ -0,0 +1,61 @@
+use crate::pomo::state::SessionMode;
+use chrono::{DateTime, Local};
+use directories::ProjectDirs;
+use serde::{ Serialize, Deserialize };
+use std::{ fs::{self, OpenOptions}, io::Write, path::{Path, PathBuf} };
+
+// One finished (or abandoned) block, stored as a single JSON line
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionRecord {
+    pub started_at: DateTime<Local>,
+    pub ended_at: DateTime<Local>,
+    pub mode: SessionMode,
+    pub planned_secs: u64,
+    pub actual_secs: u64,
+    pub completed: bool,
+    pub task: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>
+}
+
+pub fn history_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "pomoru").map(|dirs| dirs.data_dir().join("history.jsonl"))
+}
+
+pub fn append(record: &SessionRecord) -> Result<(), Box<dyn std::error::Error>> {
+    let path = history_path().ok_or("Could not find data directory")?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // A block that ran out while pomoru was closed is fast-forwarded by whichever
+    // process loads the session first; only the first one gets to log it
+    if load_recent(&path, 8).iter().any(|r| r.started_at == record.started_at && r.mode == record.mode) {
+        return Ok(());
+    }
+
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+
+    // Single write of a whole line keeps concurrent appends from interleaving
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+fn load_recent(path: &Path, count: usize) -> Vec<SessionRecord> {
+    let Ok(content) = fs::read_to_string(path) else { return Vec::new() };
+    content.lines().rev().take(count)
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+pub fn load() -> Vec<SessionRecord> {
+    let Some(path) = history_path() else { return Vec::new() };
+    let Ok(content) = fs::read_to_string(path) else { return Vec::new() };
+
+    // A torn last line (e.g. after a crash) is skipped rather than failing the whole log
+    content.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
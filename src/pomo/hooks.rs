@@ -0,0 +1,105 @@
+use crate::pomo::state::{Pomo, SessionMode};
+use serde::{ Serialize, Deserialize };
+use std::{ process::Stdio, time::Duration };
+use tokio::process::Command;
+
+#[derive(Clone, Copy)]
+pub enum HookEvent { FocusStart, FocusEnd, BreakStart, BreakEnd, Pause, Resume, TaskDone }
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::FocusStart => "focus_start",
+            HookEvent::FocusEnd => "focus_end",
+            HookEvent::BreakStart => "break_start",
+            HookEvent::BreakEnd => "break_end",
+            HookEvent::Pause => "pause",
+            HookEvent::Resume => "resume",
+            HookEvent::TaskDone => "task_done",
+        }
+    }
+}
+
+// Shell commands run through `sh -c` on session transitions
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_focus_start: Option<String>,
+    pub on_focus_end: Option<String>,
+    pub on_break_start: Option<String>,
+    pub on_break_end: Option<String>,
+    pub on_pause: Option<String>,
+    pub on_resume: Option<String>,
+    pub on_task_done: Option<String>,
+    pub timeout_secs: u64
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Self {
+            on_focus_start: None,
+            on_focus_end: None,
+            on_break_start: None,
+            on_break_end: None,
+            on_pause: None,
+            on_resume: None,
+            on_task_done: None,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl Hooks {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn command(&self, event: HookEvent) -> Option<&str> {
+        let command = match event {
+            HookEvent::FocusStart => &self.on_focus_start,
+            HookEvent::FocusEnd => &self.on_focus_end,
+            HookEvent::BreakStart => &self.on_break_start,
+            HookEvent::BreakEnd => &self.on_break_end,
+            HookEvent::Pause => &self.on_pause,
+            HookEvent::Resume => &self.on_resume,
+            HookEvent::TaskDone => &self.on_task_done,
+        };
+        command.as_deref().filter(|c| !c.trim().is_empty())
+    }
+}
+
+impl Pomo {
+    // Fires the hook in the background; a hook that outlives its timeout is killed
+    pub fn run_hook(&self, event: HookEvent, task: Option<&str>) {
+        let Some(command) = self.hooks.command(event) else { return };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else { return };
+
+        let mode = match self.mode {
+            SessionMode::Work => "work",
+            SessionMode::ShortBreak => "short_break",
+            SessionMode::LongBreak => "long_break",
+        };
+        let task = task.or(self.focus_task().map(|t| t.title.as_str())).unwrap_or("");
+
+        let mut child = Command::new("sh");
+        child.arg("-c").arg(command)
+            .env("POMORU_EVENT", event.name())
+            .env("POMORU_MODE", mode)
+            .env("POMORU_TASK", task)
+            .env("POMORU_REMAINING_SECS", self.time_remaining.as_secs().to_string())
+            .env("POMORU_DURATION_SECS", self.total_duration.as_secs().to_string())
+            .env("POMORU_COUNT", self.break_count.to_string())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let timeout = Duration::from_secs(self.hooks.timeout_secs);
+        runtime.spawn(async move {
+            let Ok(mut child) = child.spawn() else { return };
+            if tokio::time::timeout(timeout, child.wait()).await.is_err() {
+                let _ = child.kill().await;
+            }
+        });
+    }
+}
@@ -0,0 +1,457 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{ Serialize, Deserialize };
+use std::collections::BTreeMap;
+
+// Every remappable command. Which ones are available depends on the KeyContext.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ToggleTimer,
+    CycleMode,
+    EditTime,
+    Reset,
+    OpenTasks,
+    OpenStats,
+    Quit,
+    Back,
+    InsertTask,
+    InsertSubtask,
+    EditTask,
+    DeleteTask,
+    ToggleTask,
+    NextTask,
+    PreviousTask,
+    MoveTaskUp,
+    MoveTaskDown,
+    CyclePriority,
+    SortTasks,
+    PinTask,
+    IndentTask,
+    OutdentTask,
+    ToggleCollapse,
+    FilterTasks,
+    ClearDone,
+    OpenArchive,
+    Undo,
+    Redo,
+    ImportTasks,
+    ExportTasks,
+    IncreaseEstimate,
+    DecreaseEstimate,
+    NextRange,
+    PreviousRange,
+    FinishBlock,
+    VoidBlock,
+    Continue,
+    Confirm,
+    Cancel,
+    CursorLeft,
+    CursorRight,
+    LineStart,
+    LineEnd,
+    WordLeft,
+    WordRight,
+    DeleteBackward,
+    DeleteForward,
+    DeleteWordBackward,
+    DeleteToStart,
+    DeleteToEnd
+}
+
+impl Action {
+    // Short label used in the footer hints
+    pub fn hint(self) -> &'static str {
+        match self {
+            Action::ToggleTimer => "pause",
+            Action::CycleMode => "session",
+            Action::EditTime => "edit time",
+            Action::Reset => "reset",
+            Action::OpenTasks => "tasks",
+            Action::OpenStats => "stats",
+            Action::Quit => "quit",
+            Action::Back => "back",
+            Action::InsertTask => "insert",
+            Action::InsertSubtask => "subtask",
+            Action::EditTask => "edit",
+            Action::DeleteTask => "delete",
+            Action::ToggleTask => "toggle",
+            Action::NextTask => "down",
+            Action::PreviousTask => "up",
+            Action::MoveTaskUp => "move up",
+            Action::MoveTaskDown => "move down",
+            Action::CyclePriority => "priority",
+            Action::SortTasks => "sort",
+            Action::PinTask => "focus",
+            Action::IndentTask => "indent",
+            Action::OutdentTask => "outdent",
+            Action::ToggleCollapse => "fold",
+            Action::FilterTasks => "filter",
+            Action::ClearDone => "clear done",
+            Action::OpenArchive => "archive",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ImportTasks => "import",
+            Action::ExportTasks => "export",
+            Action::IncreaseEstimate => "estimate",
+            Action::DecreaseEstimate => "lower estimate",
+            Action::NextRange => "next range",
+            Action::PreviousRange => "previous range",
+            Action::FinishBlock => "finish block",
+            Action::VoidBlock => "void block",
+            Action::Continue => "continue",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::CursorLeft => "left",
+            Action::CursorRight => "right",
+            Action::LineStart => "start",
+            Action::LineEnd => "end",
+            Action::WordLeft => "word left",
+            Action::WordRight => "word right",
+            Action::DeleteBackward => "delete back",
+            Action::DeleteForward => "delete forward",
+            Action::DeleteWordBackward => "delete word",
+            Action::DeleteToStart => "delete to start",
+            Action::DeleteToEnd => "delete to end",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyContext { Timer, Tasks, Stats, Archive, Input, Suspend }
+
+type DefaultBindings = &'static [(Action, &'static [&'static str])];
+
+// Defaults per context, in the order their hints appear in the footer
+const DEFAULTS: &[(KeyContext, DefaultBindings)] = &[
+    (KeyContext::Timer, &[
+        (Action::CycleMode, &["tab"]),
+        (Action::OpenTasks, &["t"]),
+        (Action::OpenStats, &["s"]),
+        (Action::EditTime, &["e"]),
+        (Action::ToggleTimer, &["space"]),
+        (Action::Reset, &["r"]),
+        (Action::Quit, &["q"]),
+    ]),
+    (KeyContext::Tasks, &[
+        (Action::InsertTask, &["i"]),
+        (Action::ToggleTask, &["enter"]),
+        (Action::EditTask, &["e"]),
+        (Action::DeleteTask, &["d"]),
+        (Action::Undo, &["u"]),
+        (Action::Back, &["t", "esc", "q"]),
+        (Action::InsertSubtask, &["a"]),
+        (Action::PinTask, &["f"]),
+        (Action::CyclePriority, &["p"]),
+        (Action::IncreaseEstimate, &["+", "="]),
+        (Action::DecreaseEstimate, &["-"]),
+        (Action::IndentTask, &["tab", ">"]),
+        (Action::OutdentTask, &["shift-tab", "<"]),
+        (Action::ToggleCollapse, &["z"]),
+        (Action::FilterTasks, &["/"]),
+        (Action::SortTasks, &["s"]),
+        (Action::ClearDone, &["c"]),
+        (Action::OpenArchive, &["A"]),
+        (Action::ImportTasks, &["I"]),
+        (Action::ExportTasks, &["E"]),
+        (Action::NextTask, &["j", "down"]),
+        (Action::PreviousTask, &["k", "up"]),
+        (Action::MoveTaskDown, &["J", "shift-down"]),
+        (Action::MoveTaskUp, &["K", "shift-up"]),
+        (Action::Redo, &["ctrl-r"]),
+    ]),
+    (KeyContext::Stats, &[
+        (Action::NextRange, &["tab", "l", "right"]),
+        (Action::PreviousRange, &["shift-tab", "h", "left"]),
+        (Action::Back, &["s", "q", "esc"]),
+    ]),
+    (KeyContext::Archive, &[
+        (Action::Back, &["A", "q", "esc"]),
+        (Action::NextTask, &["j", "down"]),
+        (Action::PreviousTask, &["k", "up"]),
+    ]),
+    (KeyContext::Input, &[
+        (Action::Confirm, &["enter"]),
+        (Action::Cancel, &["esc"]),
+        (Action::CursorLeft, &["left", "ctrl-b"]),
+        (Action::CursorRight, &["right", "ctrl-f"]),
+        (Action::LineStart, &["home", "ctrl-a"]),
+        (Action::LineEnd, &["end", "ctrl-e"]),
+        (Action::WordLeft, &["alt-b", "ctrl-left"]),
+        (Action::WordRight, &["alt-f", "ctrl-right"]),
+        (Action::DeleteBackward, &["backspace"]),
+        (Action::DeleteForward, &["delete", "ctrl-d"]),
+        (Action::DeleteWordBackward, &["ctrl-w", "alt-backspace"]),
+        (Action::DeleteToStart, &["ctrl-u"]),
+        (Action::DeleteToEnd, &["ctrl-k"]),
+    ]),
+    (KeyContext::Suspend, &[
+        (Action::FinishBlock, &["f"]),
+        (Action::VoidBlock, &["v"]),
+        (Action::Continue, &["c", "esc"]),
+    ]),
+];
+
+// Actions that exist but are left out of the footer to keep it short
+const HIDDEN_HINTS: &[Action] = &[Action::NextTask, Action::PreviousTask, Action::MoveTaskDown, Action::MoveTaskUp, Action::OutdentTask, Action::DecreaseEstimate, Action::Redo];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        // Shift is already folded into the character (and into BackTab)
+        if matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code: key.code, modifiers }
+    }
+}
+
+impl KeyChord {
+    // Parses "q", "G", "space", "enter", "ctrl-r", "alt-f", "shift-tab", "f5", ...
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut shift = false;
+        let mut rest = s;
+        loop {
+            let lower = rest.to_ascii_lowercase();
+            if let Some(r) = lower.strip_prefix("ctrl-").or(lower.strip_prefix("c-")) {
+                modifiers |= KeyModifiers::CONTROL;
+                rest = &rest[rest.len() - r.len()..];
+            } else if let Some(r) = lower.strip_prefix("alt-").or(lower.strip_prefix("m-")) {
+                modifiers |= KeyModifiers::ALT;
+                rest = &rest[rest.len() - r.len()..];
+            } else if let Some(r) = lower.strip_prefix("shift-") {
+                shift = true;
+                rest = &rest[rest.len() - r.len()..];
+            } else {
+                break;
+            }
+        }
+
+        let code = match rest.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" if shift => KeyCode::BackTab,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" => KeyCode::Insert,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(f[1..].parse().unwrap_or_default())
+            }
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if shift => KeyCode::Char(c.to_ascii_uppercase()),
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => return Err(format!("Unknown key \"{}\"", s)),
+                }
+            }
+        };
+        if shift && !matches!(code, KeyCode::Char(_) | KeyCode::BackTab) {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+        Ok(Self { code, modifiers })
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "⏎".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "shift-tab".to_string(),
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Delete => "del".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::F(n) => format!("f{}", n),
+            other => format!("{:?}", other).to_lowercase(),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) { label.push_str("ctrl-"); }
+        if self.modifiers.contains(KeyModifiers::ALT) { label.push_str("alt-"); }
+        if self.modifiers.contains(KeyModifiers::SHIFT) { label.push_str("shift-"); }
+        label + &key
+    }
+}
+
+fn parse_sequence(s: &str) -> Result<Vec<KeyChord>, String> {
+    let chords = s.split_whitespace().map(KeyChord::parse).collect::<Result<Vec<_>, _>>()?;
+    if chords.is_empty() { return Err("Empty key binding".to_string()); }
+    Ok(chords)
+}
+
+// `[keys.<context>]` tables in config.toml; each action takes one binding or a list
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Bindings { One(String), Many(Vec<String>) }
+
+pub type KeyConfig = BTreeMap<KeyContext, BTreeMap<Action, Bindings>>;
+
+pub enum Resolved { Action(Action), Pending, Unbound }
+
+// An action with every key sequence bound to it
+type Binding = (Action, Vec<Vec<KeyChord>>);
+
+#[derive(Clone)]
+pub struct Keymap {
+    contexts: Vec<(KeyContext, Vec<Binding>)>
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&KeyConfig::new()).unwrap_or(Self { contexts: Vec::new() })
+    }
+}
+
+impl Keymap {
+    // Configured actions replace their default bindings; everything else keeps the
+    // defaults, minus any key a configured action took over
+    pub fn from_config(config: &KeyConfig) -> Result<Self, String> {
+        let mut contexts = Vec::new();
+        for (context, defaults) in DEFAULTS {
+            let overrides = config.get(context);
+            if let Some(overrides) = overrides
+                && let Some(action) = overrides.keys().find(|a| !defaults.iter().any(|(d, _)| d == *a)) {
+                return Err(format!("Action \"{}\" is not available in [keys.{}]", json_name(action), json_name(context)));
+            }
+
+            let mut configured: Vec<Binding> = Vec::new();
+            for (action, bindings) in overrides.into_iter().flatten() {
+                let keys = match bindings {
+                    Bindings::One(key) => vec![key.clone()],
+                    Bindings::Many(keys) => keys.clone(),
+                };
+                let sequences = keys.iter().map(|k| parse_sequence(k)).collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("[keys.{}] {}: {}", json_name(context), json_name(action), e))?;
+                // Two configured actions on one key would leave one of them unreachable
+                for (other, taken) in &configured {
+                    if let Some(seq) = sequences.iter().find(|s| taken.iter().any(|t| overlaps(s, t))) {
+                        return Err(format!("[keys.{}] {} and {} are both bound to \"{}\"",
+                            json_name(context), json_name(other), json_name(action), label(seq)));
+                    }
+                }
+                configured.push((*action, sequences));
+            }
+
+            let mut actions = Vec::new();
+            for (action, keys) in defaults.iter() {
+                if let Some((_, sequences)) = configured.iter().find(|(a, _)| a == action) {
+                    actions.push((*action, sequences.clone()));
+                    continue;
+                }
+                let sequences = keys.iter()
+                    .map(|k| parse_sequence(k))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter()
+                    .filter(|s| !configured.iter().any(|(_, taken)| taken.iter().any(|t| overlaps(s, t))))
+                    .collect();
+                actions.push((*action, sequences));
+            }
+            contexts.push((*context, actions));
+        }
+        Ok(Self { contexts })
+    }
+
+    fn actions(&self, context: KeyContext) -> &[Binding] {
+        self.contexts.iter().find(|(c, _)| *c == context).map(|(_, a)| a.as_slice()).unwrap_or(&[])
+    }
+
+    // Feeds one key into the pending sequence. A full match fires its action, a
+    // prefix of some binding waits for more keys, and anything else starts over.
+    pub fn resolve(&self, context: KeyContext, pending: &mut Vec<KeyChord>, key: KeyChord) -> Resolved {
+        pending.push(key);
+        let actions = self.actions(context);
+
+        if let Some((action, _)) = actions.iter().find(|(_, seqs)| seqs.iter().any(|s| s == pending)) {
+            pending.clear();
+            return Resolved::Action(*action);
+        }
+        if actions.iter().any(|(_, seqs)| seqs.iter().any(|s| s.starts_with(pending))) {
+            return Resolved::Pending;
+        }
+
+        // A broken sequence may still be the start of something on its own
+        let retry = pending.len() > 1;
+        pending.clear();
+        if retry { self.resolve(context, pending, key) } else { Resolved::Unbound }
+    }
+
+    // "tab session", "t tasks", ... built from the first binding of each action
+    pub fn hints(&self, context: KeyContext) -> Vec<String> {
+        self.actions(context).iter()
+            .filter(|(action, _)| !HIDDEN_HINTS.contains(action))
+            .filter_map(|(action, seqs)| {
+                let keys = label(seqs.first()?);
+                Some(format!("{} {}", keys, action.hint()))
+            })
+            .collect()
+    }
+}
+
+// Sequences that can't both be bound: the same keys, or one the start of the other
+fn overlaps(a: &[KeyChord], b: &[KeyChord]) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+fn label(sequence: &[KeyChord]) -> String {
+    sequence.iter().map(|k| k.label()).collect::<Vec<_>>().join(" ")
+}
+
+// The snake_case name as written in config.toml
+fn json_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default().trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keymap(config: &str) -> Result<Keymap, String> {
+        Keymap::from_config(&toml::from_str::<KeyConfig>(config).unwrap())
+    }
+
+    fn press(keymap: &Keymap, context: KeyContext, key: &str) -> Option<Action> {
+        match keymap.resolve(context, &mut Vec::new(), KeyChord::parse(key).unwrap()) {
+            Resolved::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn configured_key_wins_over_a_default() {
+        let keymap = keymap("[tasks]\nprevious_task = [\"p\", \"up\"]\n[timer]\ntoggle_timer = \"t\"").unwrap();
+        assert!(matches!(press(&keymap, KeyContext::Tasks, "p"), Some(Action::PreviousTask)));
+        assert!(press(&keymap, KeyContext::Tasks, "k").is_none());
+        assert!(matches!(press(&keymap, KeyContext::Timer, "t"), Some(Action::ToggleTimer)));
+        // Everything else keeps its defaults
+        assert!(matches!(press(&keymap, KeyContext::Tasks, "j"), Some(Action::NextTask)));
+    }
+
+    #[test]
+    fn same_key_on_two_configured_actions_is_an_error() {
+        let error = keymap("[tasks]\nnext_task = \"n\"\nprevious_task = [\"up\", \"n\"]").err().unwrap();
+        assert!(error.contains("\"n\""), "{}", error);
+        assert!(keymap("[tasks]\nback = \"g\"\nnext_task = \"g j\"").is_err());
+    }
+}
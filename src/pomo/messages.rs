@@ -0,0 +1,93 @@
+use rand::seq::SliceRandom;
+use serde::{ Serialize, Deserialize };
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageOrder { #[default] Random, Rotate }
+
+#[derive(Clone, Copy)]
+pub enum MessageEvent { FocusComplete, LongBreakStart, ShortBreakOver, LongBreakOver }
+
+impl MessageEvent {
+    pub const COUNT: usize = 4;
+}
+
+// Notification bodies per event. Each entry may use `{task}`, `{count}`, `{next}` and `{duration}`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Messages {
+    pub order: MessageOrder,
+    pub focus_complete: Vec<String>,
+    pub long_break_start: Vec<String>,
+    pub short_break_over: Vec<String>,
+    pub long_break_over: Vec<String>
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        let focus = [
+            "I'm tired, boss...",
+            "Congrats! You're him 🗿",
+            "Stand up. Touch grass.",
+            "Mission Passed! Respect+",
+        ];
+        let back_to_work = [
+            "Ah shit, here we go again.",
+            "Wake up, Samurai. We have code to burn.",
+            "Lock back in.",
+            "Ref! Do Something! The break's over!",
+        ];
+        let owned = |list: &[&str]| list.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+
+        Self {
+            order: MessageOrder::Random,
+            focus_complete: owned(&focus),
+            long_break_start: owned(&focus),
+            short_break_over: owned(&back_to_work),
+            long_break_over: owned(&back_to_work),
+        }
+    }
+}
+
+// Values substituted into the message templates
+pub struct MessageContext<'a> {
+    pub task: Option<&'a str>,
+    pub count: u32,
+    pub next: &'a str,
+    pub duration_mins: u64
+}
+
+impl Messages {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn list(&self, event: MessageEvent) -> &[String] {
+        match event {
+            MessageEvent::FocusComplete => &self.focus_complete,
+            MessageEvent::LongBreakStart => &self.long_break_start,
+            MessageEvent::ShortBreakOver => &self.short_break_over,
+            MessageEvent::LongBreakOver => &self.long_break_over,
+        }
+    }
+
+    // With `order = "rotate"`, each event keeps its own counter, bumped after every
+    // notification for it, so every list is walked in turn
+    pub fn pick(&self, event: MessageEvent, rotation: usize, ctx: &MessageContext) -> String {
+        let list = self.list(event);
+        let template = match self.order {
+            MessageOrder::Random => list.choose(&mut rand::thread_rng()),
+            MessageOrder::Rotate => list.get(rotation % list.len().max(1)),
+        };
+
+        template.map(|t| fill(t, ctx)).unwrap_or_default()
+    }
+}
+
+fn fill(template: &str, ctx: &MessageContext) -> String {
+    template
+        .replace("{task}", ctx.task.unwrap_or("your task"))
+        .replace("{count}", &ctx.count.to_string())
+        .replace("{next}", ctx.next)
+        .replace("{duration}", &format!("{} min", ctx.duration_mins))
+}
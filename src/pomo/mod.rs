@@ -1,53 +1,388 @@
+pub mod archive;
+pub mod daemon;
+pub mod editor;
+pub mod formats;
+pub mod history;
+pub mod hooks;
+pub mod keymap;
+pub mod messages;
 pub mod state;
+pub mod stats;
+pub mod storage;
+pub mod status;
+pub mod tasks;
+pub mod theme;
 pub mod ui;
 
-use crate::pomo::state::{Pomo, AppScreen, InputMode, Task, SessionMode, Config};
+use crate::pomo::daemon::{Request, Snapshot};
+use crate::pomo::hooks::HookEvent;
+use crate::pomo::keymap::{Action, KeyContext, Keymap, Resolved};
+use crate::pomo::state::{session_length, Pomo, AppScreen, InputMode, Task, Priority, SessionMode, Config, DataFile};
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, Event, KeyCode, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::prelude::*;
-use std::{ io, time::Duration, fs };
+use std::{ io, time::{Duration, Instant}, fs, path::{Path, PathBuf} };
 use directories::ProjectDirs;
+use tokio::sync::mpsc;
+
+pub fn default_config_path() -> Option<PathBuf> {
+    ProjectDirs::from("", "", "pomoru").map(|dirs| dirs.config_dir().join("config.toml"))
+}
+
+// Tasks and the session live in the data directory, or next to a config file
+// given with --config so that each one keeps its own list
+fn data_path_for(config_path: &Path) -> Option<PathBuf> {
+    if Some(config_path) == default_config_path().as_deref() {
+        ProjectDirs::from("", "", "pomoru").map(|dirs| dirs.data_dir().join("state.toml"))
+    } else {
+        Some(config_path.with_extension("state.toml"))
+    }
+}
+
+// An empty file would otherwise parse as an empty list, and merging that in
+// would delete every task
+fn parse_data(path: &Path, content: &str) -> Result<DataFile, String> {
+    if content.trim().is_empty() {
+        return Err(format!("{} is empty", storage::file_name(path)));
+    }
+    toml::from_str(content).map_err(|e| storage::describe_toml_error(path, content, &e))
+}
+
+// Task edits are written at most this long after they happen
+const SAVE_DELAY: Duration = Duration::from_secs(1);
 
 impl Pomo {
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+    // The data file is written every time; config.toml only when it doesn't exist
+    // yet or a preference was changed from inside pomoru, and never when it has errors
+    pub fn save(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_data()?;
+        if !self.config_broken && (self.config_dirty || self.config_path.as_ref().is_some_and(|p| !p.exists())) {
+            self.save_config()?;
+        }
+        Ok(())
+    }
+
+    pub fn save_config(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Edits made to the file since it was read go in first
+        if let Some(path) = &self.config_path && storage::changed_since(path, self.config_stamp) {
+            self.reload_config();
+        }
+        if self.config_broken { return Err("Not overwriting a config file that has errors".into()); }
         let config = Config {
             work_time_mins: self.work_time.as_secs() / 60,
             short_break_mins: self.short_break_time.as_secs() / 60,
             long_break_mins: self.long_break_time.as_secs() / 60,
-            tasks: self.tasks.clone(),
+            long_break_interval: self.long_break_interval,
+            theme: self.theme_name.clone(),
+            auto_start_breaks: self.auto_start_breaks,
+            auto_start_focus: self.auto_start_focus,
+            messages: self.messages.clone(),
+            hooks: self.hooks.clone(),
+            keys: self.keys.clone(),
+            auto_advance_task: self.auto_advance_task,
+            rollover_hour: self.rollover_hour,
         };
 
         let toml = toml::to_string_pretty(&config)?;
-        let config_dir = ProjectDirs::from("", "", "pomoru")
-            .ok_or("Could not find config directory")?
-            .config_dir()
-            .to_path_buf();
+        let config_path = self.config_path.as_ref().ok_or("Could not find config directory")?;
+        storage::write_atomic(config_path, &toml)?;
+        self.config_stamp = storage::modified(config_path);
+        self.config_dirty = false;
+        Ok(())
+    }
+
+    pub fn save_data(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Never writes over an edit made elsewhere that hasn't been merged yet
+        if let Some(path) = &self.data_path && storage::changed_since(path, self.data_stamp) {
+            self.reload_data();
+        }
+        let data = DataFile {
+            active_task: self.active_task,
+            session: Some(self.session_state()),
+            tasks: self.tasks.clone(),
+        };
 
-        fs::create_dir_all(&config_dir)?;
-        fs::write(config_dir.join("config.toml"), toml)?;
+        let toml = toml::to_string_pretty(&data)?;
+        let data_path = self.data_path.as_ref().ok_or("Could not find data directory")?;
+        let _ = storage::rotate_backups(data_path);
+        storage::write_atomic(data_path, &toml)?;
+        self.data_stamp = storage::modified(data_path);
+        self.saved_tasks = self.tasks.clone();
+        self.saved_active_task = self.active_task;
+        self.unsaved_since = None;
         Ok(())
     }
 
-    pub fn load() -> Self {
+    pub fn mark_unsaved(&mut self) {
+        self.unsaved_since.get_or_insert_with(Instant::now);
+    }
+
+    pub fn save_due(&self) -> bool {
+        self.unsaved_since.is_some_and(|t| t.elapsed() >= SAVE_DELAY)
+    }
+
+    // `config_path` overrides the default `config_dir/config.toml` location
+    pub fn load(config_path: Option<PathBuf>) -> Self {
         let mut app = Pomo::new();
-        if let Some(proj_dirs) = ProjectDirs::from("", "", "pomoru") {
-            let config_path = proj_dirs.config_dir().join("config.toml");
-            if let Ok(content) = fs::read_to_string(config_path) {
-                if let Ok(config) = toml::from_str::<Config>(&content) {
-                    app.work_time = Duration::from_secs(config.work_time_mins * 60);
-                    app.short_break_time = Duration::from_secs(config.short_break_mins * 60);
-                    app.long_break_time = Duration::from_secs(config.long_break_mins * 60);
-                    app.tasks = config.tasks;
-                    app.reset_timer_to_mode();
+        app.config_path = config_path.or_else(default_config_path);
+        app.data_path = app.config_path.as_deref().and_then(data_path_for);
+
+        let (content, config) = app.read_config();
+        app.keep_broken_config();
+        if let Some(config) = &config {
+            app.apply_config(config);
+        }
+
+        // A data file that doesn't parse falls back to the newest backup that does
+        let data_file = app.data_path.clone().filter(|p| p.exists());
+        app.data_stamp = data_file.as_deref().and_then(storage::modified);
+        let data = data_file.as_deref().and_then(|path| app.load_data(path));
+        // Before there was a data file, tasks and the session were kept in config.toml.
+        // Tasks left in a config that had errors when the data file was first
+        // written are added to the list once it loads.
+        let legacy = content.as_deref()
+            .filter(|_| config.is_some())
+            .and_then(|c| toml::from_str::<DataFile>(c).ok())
+            .filter(|d| !d.tasks.is_empty() || d.session.is_some() || d.active_task.is_some());
+        let migrate = legacy.is_some();
+        let data = match (data, legacy) {
+            (Some(mut data), Some(legacy)) => {
+                data.tasks.extend(legacy.tasks.into_iter().map(|t| Task { id: 0, ..t }));
+                data
+            }
+            (data, legacy) => data.or(legacy).unwrap_or_default(),
+        };
+
+        app.saved_tasks = data.tasks.clone();
+        app.saved_active_task = data.active_task;
+        app.tasks = data.tasks;
+        app.active_task = data.active_task;
+        app.settle_tasks();
+        // New ids and completion times are written back before anything merges against them
+        if app.tasks != app.saved_tasks || app.active_task != app.saved_active_task {
+            app.mark_unsaved();
+        }
+        app.reset_timer_to_mode();
+        if let Some(session) = data.session {
+            app.restore_session(session);
+        }
+
+        // Rewriting the config drops the old fields from it
+        if migrate && app.save_data().is_ok() {
+            let _ = app.save_config();
+        }
+        app
+    }
+
+    // A missing config is a first run. One that can't be read or parsed is
+    // reported, copied to config.toml.bak and left alone; defaults are used meanwhile.
+    fn read_config(&mut self) -> (Option<String>, Option<Config>) {
+        let Some(path) = self.config_path.clone() else { return (None, None) };
+        self.config_stamp = storage::modified(&path);
+        match fs::read_to_string(&path) {
+            Ok(content) => match toml::from_str::<Config>(&content) {
+                Ok(config) => (Some(content), Some(config)),
+                Err(e) => {
+                    self.config_failed(storage::describe_toml_error(&path, &content, &e));
+                    (Some(content), None)
                 }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (None, None),
+            Err(e) => {
+                self.config_failed(format!("Could not read {}: {}", path.display(), e));
+                (None, None)
+            }
+        }
+    }
+
+    fn apply_config(&mut self, config: &Config) {
+        let Some(config_path) = self.config_path.clone() else { return };
+        let name = storage::file_name(&config_path);
+        match (session_length(config.work_time_mins), session_length(config.short_break_mins), session_length(config.long_break_mins)) {
+            (Ok(work), Ok(short), Ok(long)) => {
+                self.work_time = work;
+                self.short_break_time = short;
+                self.long_break_time = long;
+            }
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                self.config_errors.push(format!("{}: {}; keeping the previous lengths", name, e));
+            }
+        }
+        self.long_break_interval = config.long_break_interval;
+        match theme::load(&config.theme, config_path.parent().map(|dir| dir.join("themes")).as_deref()) {
+            Ok(theme) => self.theme = theme,
+            Err(e) => self.config_errors.push(format!("{}: {}; using the default theme", name, e)),
+        }
+        self.theme_name = config.theme.clone();
+        self.auto_start_breaks = config.auto_start_breaks;
+        self.auto_start_focus = config.auto_start_focus;
+        self.messages = config.messages.clone();
+        self.hooks = config.hooks.clone();
+        match Keymap::from_config(&config.keys) {
+            Ok(keymap) => self.keymap = keymap,
+            Err(e) => self.config_errors.push(format!("{}: {}; using the default keys", name, e)),
+        }
+        self.keys = config.keys.clone();
+        self.auto_advance_task = config.auto_advance_task;
+        self.rollover_hour = config.rollover_hour;
+    }
+
+    // Picks up whatever changed on disk since pomoru last read or wrote it. An
+    // attached TUI leaves the data file to the daemon.
+    pub fn reload_changed(&mut self) {
+        if let Some(path) = &self.config_path && storage::changed_since(path, self.config_stamp) {
+            self.reload_config();
+        }
+        if self.remote.is_none() && let Some(path) = &self.data_path && storage::changed_since(path, self.data_stamp) {
+            self.reload_data();
+        }
+    }
+
+    // Applies edits made to config.toml while pomoru runs. A block in progress
+    // keeps its length and remaining time; new lengths start with the next one.
+    fn reload_config(&mut self) {
+        let lengths = (self.work_time, self.short_break_time, self.long_break_time);
+        self.config_errors.clear();
+        self.config_broken = false;
+        let (_, config) = self.read_config();
+        self.keep_broken_config();
+        let Some(config) = config else { return };
+        self.apply_config(&config);
+        // A length changed from inside pomoru that isn't saved yet wins
+        if self.config_dirty {
+            (self.work_time, self.short_break_time, self.long_break_time) = lengths;
+        }
+        if !self.is_running && self.time_remaining == self.total_duration {
+            self.reset_timer_to_mode();
+        }
+    }
+
+    // Merges edits made to the data file outside pomoru, e.g. by a sync tool or
+    // `pomoru tasks` without the daemon, with the ones made here since the last save
+    fn reload_data(&mut self) {
+        let Some(path) = self.data_path.clone() else { return };
+        self.data_stamp = storage::modified(&path);
+        let Ok(content) = fs::read_to_string(&path) else { return };
+        let data = match parse_data(&path, &content) {
+            Ok(data) => data,
+            Err(e) => {
+                self.load_errors.push(e);
+                storage::keep_copy(&path);
+                return;
+            }
+        };
+
+        let selected = self.selected_task().map(|i| self.tasks[i].id);
+        let merged = tasks::merge(&self.saved_tasks, &self.tasks, &data.tasks);
+        if merged != self.tasks {
+            self.tasks = merged;
+            // Undoing past an outside edit would quietly take it back
+            self.undo.clear();
+            self.redo.clear();
+        }
+        if self.active_task == self.saved_active_task {
+            self.active_task = data.active_task;
+        }
+        self.saved_tasks = data.tasks;
+        self.saved_active_task = data.active_task;
+        self.settle_tasks();
+        self.select_task(selected.and_then(|id| self.tasks.iter().position(|t| t.id == id)));
+
+        // Edits from this side still have to be written
+        if self.tasks != self.saved_tasks || self.active_task != self.saved_active_task {
+            self.mark_unsaved();
+        }
+    }
+
+    // The data file, if it changed since the last call and parses
+    pub fn read_data_if_changed(&mut self) -> Option<DataFile> {
+        let path = self.data_path.clone()?;
+        if !storage::changed_since(&path, self.data_stamp) { return None; }
+        self.data_stamp = storage::modified(&path);
+        parse_data(&path, &fs::read_to_string(&path).ok()?).ok()
+    }
+
+    fn config_failed(&mut self, error: String) {
+        self.config_errors.push(error);
+        self.config_broken = true;
+    }
+
+    fn keep_broken_config(&self) {
+        if self.config_broken && let Some(path) = &self.config_path {
+            storage::keep_copy(path);
+        }
+    }
+
+    // Preferences only, without writing anything: no .bak copies, no migration,
+    // no session restored into the history
+    pub fn load_preferences(config_path: Option<PathBuf>) -> Self {
+        let mut app = Pomo::new();
+        app.config_path = config_path.or_else(default_config_path);
+        app.data_path = app.config_path.as_deref().and_then(data_path_for);
+        let (_, config) = app.read_config();
+        if let Some(config) = &config {
+            app.apply_config(config);
+        }
+        app.reset_timer_to_mode();
+        app
+    }
+
+    // Reports what `load` would, without writing anything
+    pub fn check(config_path: Option<PathBuf>) -> Self {
+        let mut app = Pomo::load_preferences(config_path);
+        if let Some(path) = app.data_path.clone().filter(|p| p.exists()) {
+            match fs::read_to_string(&path) {
+                Ok(content) => if let Err(e) = parse_data(&path, &content) { app.load_errors.push(e) },
+                Err(e) => app.load_errors.push(format!("Could not read {}: {}", path.display(), e)),
             }
         }
         app
     }
 
+    // The data file or, when it doesn't parse, the newest backup that does. The
+    // unreadable file is kept as state.toml.bak before a save replaces it.
+    fn load_data(&mut self, path: &Path) -> Option<DataFile> {
+        let mut failed = None;
+        for candidate in storage::with_backups(path) {
+            let Ok(content) = fs::read_to_string(&candidate) else { continue };
+            match parse_data(&candidate, &content) {
+                Ok(data) => {
+                    if let Some(error) = failed {
+                        self.load_errors.push(format!("{}; loaded {} instead", error, storage::file_name(&candidate)));
+                        storage::keep_copy(path);
+                    }
+                    return Some(data);
+                }
+                Err(e) if failed.is_none() => failed = Some(e),
+                Err(_) => {}
+            }
+        }
+        if let Some(error) = failed {
+            self.load_errors.push(format!("{}; no backup could be read, starting with an empty list", error));
+            storage::keep_copy(path);
+        }
+        None
+    }
+
+    // Everything the startup banner and stderr report, config first
+    pub fn load_problems(&self) -> Vec<&str> {
+        self.config_errors.iter().chain(&self.load_errors).map(String::as_str).collect()
+    }
+
+    pub fn clear_load_problems(&mut self) {
+        self.config_errors.clear();
+        self.load_errors.clear();
+    }
+
+    // What to say after the errors, wherever they are shown
+    pub fn load_error_note(&self) -> Option<String> {
+        let name = storage::file_name(self.config_path.as_deref()?);
+        self.config_broken.then(|| format!("Using default settings; {} is left as it is, with a copy in {}.bak", name, name))
+    }
+
     pub async fn run(&mut self) -> io::Result<()> {
         enable_raw_mode()?;
         let mut stdout = io::stdout();
@@ -55,29 +390,54 @@ impl Pomo {
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
-        let mut second_tick = tokio::time::interval(Duration::from_secs(1));
+        // The clock is wall-time based, so this only controls how often the display refreshes
+        let mut second_tick = tokio::time::interval(Duration::from_millis(250));
+        second_tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        let mut last_session_key = self.session_key();
+        let mut remote_snapshots = self.remote_snapshots.take();
 
         while !self.should_quit {
             terminal.draw(|f| ui::render(f, self))?;
 
             tokio::select! {
                 _ = second_tick.tick() => {
-                    self.tick();
+                    self.reload_changed();
+                    // An attached daemon does its own ticking and rollover
+                    if self.remote.is_none() {
+                        self.tick();
+                        if self.roll_over() { let _ = self.save(); }
+                    }
                 }
- 
+
                 // Tighten poll to 16ms (~60fps feel) for input responsiveness
                 event_res = tokio::task::spawn_blocking(|| event::poll(Duration::from_millis(16))) => {
-                    if let Ok(Ok(true)) = event_res {
-                        if let Ok(Event::Key(key)) = event::read() {
-                            if key.kind == event::KeyEventKind::Press {
-                                self.handle_key(key);
-                            }
+                    if let Ok(Ok(true)) = event_res
+                        && let Ok(Event::Key(key)) = event::read()
+                        && key.kind == event::KeyEventKind::Press {
+                        let tasks_before = self.tasks.clone();
+                        self.handle_key(key);
+
+                        // Task edits made while attached are pushed to the daemon as a whole list,
+                        // along with the list they were made to
+                        if let Some(remote) = &self.remote && tasks_before != self.tasks {
+                            let _ = remote.send(Request::SetTasks { tasks: self.tasks.clone(), base: Some(tasks_before) });
                         }
                     }
                 }
+
+                snapshot = recv_snapshot(&mut remote_snapshots) => match snapshot {
+                    Some(snapshot) => self.apply_snapshot(snapshot),
+                    // The daemon went away; carry on locally from what it last persisted
+                    None => self.detach_remote(),
+                }
             }
 
-            if self.should_quit {
+            // Persist on every start, pause or transition so a crash doesn't lose the
+            // block, and shortly after any change to the tasks
+            let session_key = self.session_key();
+            if self.remote.is_none() && (self.should_quit || session_key != last_session_key || self.save_due()) {
+                last_session_key = session_key;
                 let _ = self.save();
             }
         }
@@ -87,115 +447,304 @@ impl Pomo {
         Ok(())
     }
 
+    fn detach_remote(&mut self) {
+        let screen = self.screen;
+        *self = Pomo::load(self.config_path.take());
+        self.screen = screen;
+    }
+
+    pub fn key_context(&self) -> KeyContext {
+        match (self.input_mode, self.screen) {
+            (InputMode::SuspendPrompt, _) => KeyContext::Suspend,
+            (InputMode::Normal, AppScreen::Timer) => KeyContext::Timer,
+            (InputMode::Normal, AppScreen::Tasks) => KeyContext::Tasks,
+            (InputMode::Normal, AppScreen::Stats) => KeyContext::Stats,
+            (InputMode::Normal, AppScreen::Archive) => KeyContext::Archive,
+            _ => KeyContext::Input,
+        }
+    }
+
     fn handle_key(&mut self, key: event::KeyEvent) {
-        match self.input_mode {
-            InputMode::Normal => match (self.screen, key.code) {
-                (AppScreen::Tasks, KeyCode::Char('q')) => self.screen = AppScreen::Timer,
-                (AppScreen::Timer, KeyCode::Char('q')) => self.should_quit = true,
-
-                (AppScreen::Timer, KeyCode::Tab) => {
-                    if !self.is_running {
-                        self.mode = match self.mode {
-                            SessionMode::Work => SessionMode::ShortBreak,
-                            SessionMode::ShortBreak => SessionMode::LongBreak,
-                            SessionMode::LongBreak => SessionMode::Work,
-                        };
-                        self.reset_timer_to_mode();
-                    }
-                }
+        // The first key only dismisses the startup banner
+        if !self.load_problems().is_empty() {
+            self.clear_load_problems();
+            return;
+        }
+        self.notice = None;
+        let context = self.key_context();
+        let before = self.undo_state();
+        let active_before = self.active_task;
+        let resolved = self.keymap.resolve(context, &mut self.pending_keys, key.into());
+        match resolved {
+            Resolved::Action(action) => self.perform(action),
+            // Unbound keys in the input modal are text
+            Resolved::Unbound if context == KeyContext::Input => self.handle_input_mode(key),
+            Resolved::Unbound | Resolved::Pending => {}
+        }
+        self.settle_tasks();
 
-                (AppScreen::Timer, KeyCode::Char('e')) => {
-                    if !self.is_running {
-                        self.input_mode = InputMode::TimerEdit;
-                        self.input_buffer = (self.time_remaining.as_secs() / 60).to_string();
-                    }
-                }
+        let changed = self.tasks != before.tasks;
+        if changed || self.active_task != active_before {
+            self.mark_unsaved();
+        }
+        // Any key that changed the list can be undone, except undo and redo
+        // themselves and archiving, which can't be taken back
+        if changed && !matches!(resolved, Resolved::Action(Action::Undo | Action::Redo | Action::ClearDone)) {
+            self.push_undo(before);
+        }
+    }
 
-                (AppScreen::Timer, KeyCode::Char('t')) => self.screen = AppScreen::Tasks,
-                (AppScreen::Timer, KeyCode::Char(' ')) => self.is_running = !self.is_running,
-                (AppScreen::Timer, KeyCode::Char('r')) => self.time_remaining = self.work_time,
-                (AppScreen::Tasks, KeyCode::Char('t')) | (AppScreen::Tasks, KeyCode::Esc) => self.screen = AppScreen::Timer,
-                (AppScreen::Tasks, KeyCode::Char('i')) => { self.input_mode = InputMode::Insert; self.input_buffer.clear(); }
-                (AppScreen::Tasks, KeyCode::Char('e')) => self.enter_edit_mode(),
-                (AppScreen::Tasks, KeyCode::Char('d')) => self.delete_task(),
-                (AppScreen::Tasks, KeyCode::Char('j')) | (AppScreen::Tasks, KeyCode::Down) => self.next_task(),
-                (AppScreen::Tasks, KeyCode::Char('k')) | (AppScreen::Tasks, KeyCode::Up) => self.previous_task(),
-                (AppScreen::Tasks, KeyCode::Enter) => self.toggle_task(),
-                _ => {}
-            },
-            _ => self.handle_input_mode(key),
+    fn perform(&mut self, action: Action) {
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Back if self.screen == AppScreen::Archive => self.screen = AppScreen::Tasks,
+            Action::Back => self.screen = AppScreen::Timer,
+
+            Action::CycleMode if !self.is_running => {
+                let mode = match self.mode {
+                    SessionMode::Work => SessionMode::ShortBreak,
+                    SessionMode::ShortBreak => SessionMode::LongBreak,
+                    SessionMode::LongBreak => SessionMode::Work,
+                };
+                self.command(Request::SetMode { mode });
+            }
+
+            Action::EditTime if !self.is_running => {
+                self.input_mode = InputMode::TimerEdit;
+                self.input.set((self.time_remaining.as_secs() / 60).to_string());
+            }
+
+            Action::OpenTasks => self.screen = AppScreen::Tasks,
+            Action::OpenStats => {
+                self.history = history::load();
+                self.screen = AppScreen::Stats;
+            }
+            Action::ToggleTimer => self.command(Request::Toggle),
+            Action::Reset => self.command(Request::Reset),
+            Action::InsertTask => { self.input_mode = InputMode::Insert; self.input.clear(); }
+            Action::InsertSubtask if self.selected_task().is_some() => {
+                self.input_mode = InputMode::InsertSubtask;
+                self.input.clear();
+            }
+            Action::IndentTask => self.indent_task(),
+            Action::OutdentTask => self.outdent_task(),
+            Action::ToggleCollapse => self.toggle_collapse(),
+            Action::FilterTasks => {
+                self.input_mode = InputMode::Filter;
+                self.input.set(self.task_filter.clone());
+            }
+            Action::EditTask => self.enter_edit_mode(),
+            Action::DeleteTask => self.delete_task(),
+            Action::NextTask if self.screen == AppScreen::Archive => self.archive_state.select_next(),
+            Action::PreviousTask if self.screen == AppScreen::Archive => self.archive_state.select_previous(),
+            Action::NextTask => self.next_task(),
+            Action::PreviousTask => self.previous_task(),
+            Action::ClearDone => if let Err(e) = self.clear_done() { self.notice = Some(Err(e)); },
+            Action::ImportTasks => { self.input_mode = InputMode::Import; self.input.clear(); }
+            Action::ExportTasks => { self.input_mode = InputMode::Export; self.input.clear(); self.confirm_overwrite = None; }
+            Action::Undo => self.undo(),
+            Action::Redo => self.redo(),
+            Action::OpenArchive => {
+                self.archive = tasks::newest_first(archive::load());
+                self.archive_state.select((!self.archive.is_empty()).then_some(0));
+                self.screen = AppScreen::Archive;
+            }
+            Action::MoveTaskUp => self.move_task(-1),
+            Action::MoveTaskDown => self.move_task(1),
+            Action::CyclePriority => self.cycle_priority(),
+            Action::SortTasks => self.sort_tasks(),
+            Action::PinTask => self.pin_task(),
+            Action::IncreaseEstimate => self.adjust_estimate(1),
+            Action::DecreaseEstimate => self.adjust_estimate(-1),
+            Action::ToggleTask => self.toggle_task(),
+            Action::NextRange => self.stats_range = self.stats_range.next(),
+            Action::PreviousRange => self.stats_range = self.stats_range.previous(),
+
+            Action::FinishBlock => {
+                self.input_mode = InputMode::Normal;
+                self.complete_session();
+            }
+            Action::VoidBlock => {
+                self.input_mode = InputMode::Normal;
+                self.void_session();
+            }
+            Action::Continue => {
+                self.input_mode = InputMode::Normal;
+                self.suspend_gap = Duration::ZERO;
+            }
+
+            Action::Confirm => self.submit_input(),
+            Action::Cancel => self.input_mode = InputMode::Normal,
+            Action::CursorLeft => self.input.move_left(),
+            Action::CursorRight => self.input.move_right(),
+            Action::LineStart => self.input.move_home(),
+            Action::LineEnd => self.input.move_end(),
+            Action::WordLeft => self.input.move_word_left(),
+            Action::WordRight => self.input.move_word_right(),
+            Action::DeleteBackward => self.input.delete_backward(),
+            Action::DeleteForward => self.input.delete_forward(),
+            Action::DeleteWordBackward => self.input.delete_word_backward(),
+            Action::DeleteToStart => self.input.delete_to_start(),
+            Action::DeleteToEnd => self.input.delete_to_end(),
+            _ => {}
         }
     }
 
-    fn handle_input_mode(&mut self, key: event::KeyEvent) {
-        match key.code {
-            KeyCode::Enter => {
-                if !self.input_buffer.is_empty() {
-                    match self.input_mode {
-                        InputMode::TimerEdit => {
-                            if let Ok(mins) = self.input_buffer.parse::<u64>() {
-                                let new_dur = Duration::from_secs(mins * 60);
-                                match self.mode {
-                                    SessionMode::Work => self.work_time = new_dur,
-                                    SessionMode::ShortBreak => self.short_break_time = new_dur,
-                                    SessionMode::LongBreak => self.long_break_time = new_dur,
-                                }
-                                self.time_remaining = new_dur;
-                                self.total_duration = new_dur;
-                            }
-                        }
+    fn submit_input(&mut self) {
+        // Only `+project` / `#tag` words and no title is not a task
+        let text = self.input.text().to_string();
+        let entry = Task::parse(&text);
+        if !text.is_empty() {
+            match self.input_mode {
+                InputMode::TimerEdit => {
+                    if let Ok(mins) = text.parse::<u64>() {
+                        self.command(Request::SetDuration { mode: self.mode, mins, temporary: false });
+                    }
+                }
 
-                        InputMode::Insert => self.tasks.push(Task { title: self.input_buffer.clone(), is_done: false }),
+                InputMode::Insert if !entry.title.is_empty() => self.tasks.push(entry),
+                InputMode::InsertSubtask if !entry.title.is_empty() => self.add_subtask(entry),
 
-                        InputMode::Edit => if let Some(i) = self.task_state.selected() { 
-                            self.tasks[i].title = self.input_buffer.clone(); 
-                        }
+                InputMode::Edit if !entry.title.is_empty() => if let Some(i) = self.selected_task() { 
+                    self.tasks[i].set_entry(&text);
+                }
 
-                        _ => {}
+                InputMode::Import => {
+                    let result = formats::read_file(&formats::expand_home(&text), None);
+                    self.notice = Some(match result {
+                        Ok(imported) => {
+                            let message = format!("Imported {} tasks from {}", imported.len(), text);
+                            self.tasks.extend(imported);
+                            Ok(message)
+                        }
+                        Err(e) => Err(format!("Import failed: {}", e)),
+                    });
+                }
+                InputMode::Export => {
+                    let path = formats::expand_home(&text);
+                    // Replacing a file takes a second confirm, with the modal left open
+                    if formats::replaces_file(&path, None) && self.confirm_overwrite.as_ref() != Some(&path) {
+                        self.confirm_overwrite = Some(path);
+                        return;
                     }
+                    let result = formats::write_file(&path, &self.tasks, None, false);
+                    self.notice = Some(match result {
+                        Ok(written) => Ok(format!("Exported {} tasks to {}", written, text)),
+                        Err(e) => Err(format!("Export failed: {}", e)),
+                    });
                 }
-                self.input_mode = InputMode::Normal;
+
+                _ => {}
             }
-            KeyCode::Esc => self.input_mode = InputMode::Normal,
-            KeyCode::Backspace => { self.input_buffer.pop(); }
-            KeyCode::Char(c) => { self.input_buffer.push(c); }
-            _ => {}
+        }
+        // An empty filter clears it
+        if self.input_mode == InputMode::Filter {
+            self.set_task_filter(&text);
+        }
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn handle_input_mode(&mut self, key: event::KeyEvent) {
+        // Unbound control and alt chords aren't text
+        if let KeyCode::Char(c) = key.code && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            self.input.insert(c);
         }
     }
 
     fn enter_edit_mode(&mut self) {
-        if let Some(i) = self.task_state.selected() {
+        if let Some(i) = self.selected_task() {
             self.input_mode = InputMode::Edit;
-            self.input_buffer = self.tasks[i].title.clone();
+            self.input.set(self.tasks[i].entry());
         }
     }
 
+    // Takes the task's subtasks with it
     fn delete_task(&mut self) {
-        if let Some(i) = self.task_state.selected() {
-            self.tasks.remove(i);
-            if self.tasks.is_empty() { self.task_state.select(None); }
+        if let Some(i) = self.selected_task() {
+            let end = tasks::subtree_end(&self.tasks, i);
+            self.tasks.drain(i..end);
+            self.select_task(if self.tasks.is_empty() { None } else { Some(i.min(self.tasks.len() - 1)) });
         }
     }
 
+    // Toggling a parent marks all of its subtasks
     fn toggle_task(&mut self) {
-        if let Some(i) = self.task_state.selected() {
-            self.tasks[i].is_done = !self.tasks[i].is_done;
+        if let Some(i) = self.selected_task() {
+            let done = !self.tasks[i].is_done;
+            tasks::set_done(&mut self.tasks, i, done);
+            if self.tasks[i].is_done {
+                self.run_hook(HookEvent::TaskDone, Some(&self.tasks[i].title));
+            }
+        }
+    }
+
+    // Adds a subtask as the last child of the selected task
+    fn add_subtask(&mut self, task: Task) {
+        let Some(i) = self.selected_task() else { return };
+        let end = tasks::subtree_end(&self.tasks, i);
+        self.tasks.insert(end, Task { depth: self.tasks[i].depth + 1, ..task });
+        self.tasks[i].collapsed = false;
+        self.select_task(Some(end));
+    }
+
+    fn cycle_priority(&mut self) {
+        if let Some(task) = self.selected_task().and_then(|i| self.tasks.get_mut(i)) {
+            task.priority = match task.priority {
+                None => Some(Priority::High),
+                Some(Priority::High) => Some(Priority::Medium),
+                Some(Priority::Medium) => Some(Priority::Low),
+                Some(Priority::Low) => None,
+            };
         }
     }
 
+    // Pins the selected task as the focus, or unpins it if it already is
+    fn pin_task(&mut self) {
+        let Some(task) = self.selected_task().and_then(|i| self.tasks.get(i)) else { return };
+        if task.is_done { return; }
+        let id = (self.active_task != Some(task.id)).then_some(task.id);
+        self.command(Request::SetActiveTask { id });
+    }
+
+    // Stepping below one pomodoro clears the estimate
+    fn adjust_estimate(&mut self, step: i32) {
+        if let Some(task) = self.selected_task().and_then(|i| self.tasks.get_mut(i)) {
+            let estimate = task.estimate.unwrap_or(0).saturating_add_signed(step);
+            task.estimate = (estimate > 0).then_some(estimate);
+        }
+    }
+
+    // Stable, so tasks of equal priority keep the order they were arranged in
+    fn sort_tasks(&mut self) {
+        let selected = self.selected_task().map(|i| self.tasks[i].id);
+        tasks::sort(&mut self.tasks, chrono::Local::now().date_naive());
+        self.select_task(self.tasks.iter().position(|t| Some(t.id) == selected));
+    }
+
     fn next_task(&mut self) {
+        let rows = self.task_rows().len();
+        if rows == 0 { return; }
         let i = match self.task_state.selected() {
-            Some(i) => if i >= self.tasks.len() - 1 { 0 } else { i + 1 },
+            Some(i) => if i >= rows - 1 { 0 } else { i + 1 },
             None => 0,
         };
         self.task_state.select(Some(i));
     }
 
     fn previous_task(&mut self) {
+        let rows = self.task_rows().len();
+        if rows == 0 { return; }
         let i = match self.task_state.selected() {
-            Some(i) => if i == 0 { self.tasks.len() - 1 } else { i - 1 },
+            Some(i) => if i == 0 { rows - 1 } else { i - 1 },
             None => 0,
         };
         self.task_state.select(Some(i));
     }
 }
+
+async fn recv_snapshot(snapshots: &mut Option<mpsc::UnboundedReceiver<Snapshot>>) -> Option<Snapshot> {
+    match snapshots {
+        Some(rx) => rx.recv().await,
+        None => std::future::pending().await,
+    }
+}
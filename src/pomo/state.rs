@@ -1,29 +1,191 @@
 use ratatui::widgets::ListState;
-use std::time::Duration;
+use std::{ path::PathBuf, time::{Duration, Instant, SystemTime} };
 use serde::{ Serialize, Deserialize };
 use notify_rust::Notification;
+use chrono::{DateTime, Local, NaiveDate};
+use crate::pomo::history::{self, SessionRecord};
+use crate::pomo::hooks::{HookEvent, Hooks};
+use crate::pomo::keymap::{KeyChord, KeyConfig, Keymap};
+use crate::pomo::messages::{MessageContext, MessageEvent, Messages};
+use crate::pomo::stats::StatsRange;
+use crate::pomo::tasks::{self, UndoState};
+use crate::pomo::theme::{Theme, DEFAULT_THEME};
+use crate::pomo::daemon::{Request, Snapshot};
+use crate::pomo::editor::LineEditor;
+use tokio::sync::mpsc;
 
-#[derive(PartialEq, Clone, Copy)]
+// Wall-clock jumps larger than this between two ticks are treated as a system suspend
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(60);
+
+// Longest block pomoru accepts, far below where deadline arithmetic would overflow
+pub const MAX_SESSION_MINS: u64 = 24 * 60;
+
+pub fn session_length(mins: u64) -> Result<Duration, String> {
+    mins.checked_mul(60)
+        .filter(|_| (1..=MAX_SESSION_MINS).contains(&mins))
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Session length must be 1 to {} minutes, not {}", MAX_SESSION_MINS, mins))
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
 pub enum SessionMode { Work, ShortBreak, LongBreak }
 
+impl SessionMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            SessionMode::Work => "Focus",
+            SessionMode::ShortBreak => "Short Break",
+            SessionMode::LongBreak => "Long Break",
+        }
+    }
+}
+
 #[derive(PartialEq, Clone, Copy)]
-pub enum AppScreen { Timer, Tasks }
+pub enum AppScreen { Timer, Tasks, Stats, Archive }
 
 #[derive(PartialEq, Clone, Copy)]
-pub enum InputMode { Normal, Insert, Edit, TimerEdit }
+pub enum InputMode { Normal, Insert, InsertSubtask, Edit, Filter, Import, Export, TimerEdit, SuspendPrompt }
 
 #[derive(Serialize, Deserialize)]
 pub struct Config {
     pub work_time_mins: u64,
     pub short_break_mins: u64,
     pub long_break_mins: u64,
+    #[serde(default = "default_long_break_interval")]
+    pub long_break_interval: u32,
+    #[serde(default = "default_theme")]
+    pub theme: String,
+    #[serde(default = "default_true")]
+    pub auto_start_breaks: bool,
+    #[serde(default = "default_true")]
+    pub auto_start_focus: bool,
+    #[serde(default, skip_serializing_if = "Messages::is_default")]
+    pub messages: Messages,
+    #[serde(default, skip_serializing_if = "Hooks::is_default")]
+    pub hooks: Hooks,
+    #[serde(default, skip_serializing_if = "KeyConfig::is_empty")]
+    pub keys: KeyConfig,
+    #[serde(default = "default_true")]
+    pub auto_advance_task: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollover_hour: Option<u32>
+}
+
+// Everything that changes as pomoru is used, kept apart from the preferences in
+// `Config`. Older versions stored these fields in config.toml itself.
+#[derive(Serialize, Deserialize, Default)]
+pub struct DataFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_task: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionState>,
+    #[serde(default)]
     pub tasks: Vec<Task>
 }
 
+// The live block, persisted so a restart (or crash) picks up where it left off.
+// A running block stores its deadline; a paused one stores what was left.
 #[derive(Serialize, Deserialize, Clone)]
+pub struct SessionState {
+    pub mode: SessionMode,
+    pub break_count: u32,
+    pub is_running: bool,
+    pub remaining_secs: u64,
+    pub deadline: Option<DateTime<Local>>,
+    pub started_at: Option<DateTime<Local>>
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority { High, Medium, Low }
+
+impl Priority {
+    pub fn marker(self) -> &'static str {
+        match self {
+            Priority::High => "!!!",
+            Priority::Medium => "!!",
+            Priority::Low => "!",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Task {
+    // Stable across reordering; 0 until the task is first stored
+    #[serde(default)]
+    pub id: u64,
     pub title: String,
-    pub is_done: bool
+    pub is_done: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<NaiveDate>,
+    // Not meant to be started before this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<NaiveDate>,
+    // Planned and completed focus blocks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<u32>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pomodoros: u32,
+    // Nesting level; a task's subtasks follow it with a greater depth
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub depth: u32,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub collapsed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Local>>
+}
+
+impl Task {
+    pub fn new(title: String) -> Self {
+        Self {
+            id: 0,
+            title,
+            is_done: false,
+            priority: None,
+            project: None,
+            tags: Vec::new(),
+            due: None,
+            scheduled: None,
+            estimate: None,
+            pomodoros: 0,
+            depth: 0,
+            collapsed: false,
+            completed_at: None
+        }
+    }
+
+    // Undone before done and overdue or due today before the rest, then high to
+    // low with unprioritised tasks last, then by due date
+    pub fn sort_key(&self, today: NaiveDate) -> (bool, bool, u8, NaiveDate) {
+        let rank = match self.priority {
+            Some(Priority::High) => 0,
+            Some(Priority::Medium) => 1,
+            Some(Priority::Low) => 2,
+            None => 3,
+        };
+        let due = self.due.unwrap_or(NaiveDate::MAX);
+        (self.is_done, due > today, rank, due)
+    }
+
+    pub fn is_over_estimate(&self) -> bool {
+        self.estimate.is_some_and(|e| self.pomodoros > e)
+    }
+
+    // "3/5" against an estimate, or just the count without one
+    pub fn progress(&self) -> Option<String> {
+        match self.estimate {
+            Some(estimate) => Some(format!("{}/{}", self.pomodoros, estimate)),
+            None if self.pomodoros > 0 => Some(self.pomodoros.to_string()),
+            None => None,
+        }
+    }
 }
 
 pub struct Pomo {
@@ -33,13 +195,69 @@ pub struct Pomo {
     pub work_time: Duration,
     pub short_break_time: Duration,
     pub long_break_time: Duration,
+    // Lengths given to `pomoru start`, used over the ones above until pomoru exits
+    pub run_lengths: [Option<Duration>; 3],
+    pub long_break_interval: u32,
+    pub auto_start_breaks: bool,
+    pub auto_start_focus: bool,
+    pub messages: Messages,
+    // Position in each event's list for `order = "rotate"`
+    pub message_rotation: [usize; MessageEvent::COUNT],
+    pub hooks: Hooks,
+    pub theme_name: String,
+    pub theme: Theme,
+    pub keys: KeyConfig,
+    pub keymap: Keymap,
+    pub pending_keys: Vec<KeyChord>,
     pub time_remaining: Duration,
     pub total_duration: Duration,
     pub is_running: bool,
+    pub run_started: Option<SystemTime>,
+    pub elapsed_before: Duration,
+    pub last_tick: Option<(Instant, SystemTime)>,
+    // Time the machine slept during this block, until the suspend prompt is answered
+    pub suspend_gap: Duration,
     pub break_count: u32,
     pub tasks: Vec<Task>,
+    pub active_task: Option<u64>,
+    pub auto_advance_task: bool,
+    pub rollover_hour: Option<u32>,
     pub task_state: ListState,
-    pub input_buffer: String,
+    pub task_filter: String,
+    pub undo: Vec<UndoState>,
+    pub redo: Vec<UndoState>,
+    pub input: LineEditor,
+    pub session_started_at: Option<DateTime<Local>>,
+    pub history: Vec<SessionRecord>,
+    pub archive: Vec<Task>,
+    pub archive_state: ListState,
+    pub stats_range: StatsRange,
+    pub config_path: Option<PathBuf>,
+    pub data_path: Option<PathBuf>,
+    // Preferences changed from inside pomoru that config.toml doesn't have yet
+    pub config_dirty: bool,
+    // config.toml exists but couldn't be read, so it is never written over
+    pub config_broken: bool,
+    // How the last import or export went, shown in place of the task screen's hints
+    // until the next key; errors are `Err`
+    pub notice: Option<Result<String, String>>,
+    // Export path the user was told already exists; exporting to it again replaces it
+    pub confirm_overwrite: Option<PathBuf>,
+    // What is wrong with the config as last read, and what went wrong loading the
+    // data file; both shown until a key is pressed
+    pub config_errors: Vec<String>,
+    pub load_errors: Vec<String>,
+    // Modification times of config.toml and the data file when pomoru last read or wrote them
+    pub config_stamp: Option<SystemTime>,
+    pub data_stamp: Option<SystemTime>,
+    // The tasks and pin as the data file has them, the common ancestor when
+    // merging edits made outside pomoru with the ones made inside
+    pub saved_tasks: Vec<Task>,
+    pub saved_active_task: Option<u64>,
+    // Set by the first change to the task list since the last save
+    pub unsaved_since: Option<Instant>,
+    pub remote: Option<mpsc::UnboundedSender<Request>>,
+    pub remote_snapshots: Option<mpsc::UnboundedReceiver<Snapshot>>,
     pub should_quit: bool
 }
 
@@ -53,77 +271,357 @@ impl Pomo {
             work_time: work,
             short_break_time: Duration::from_secs(5 * 60),
             long_break_time: Duration::from_secs(15 * 60),
+            run_lengths: [None; 3],
+            long_break_interval: default_long_break_interval(),
+            auto_start_breaks: true,
+            auto_start_focus: true,
+            messages: Messages::default(),
+            message_rotation: [0; MessageEvent::COUNT],
+            hooks: Hooks::default(),
+            theme_name: default_theme(),
+            theme: Theme::default(),
+            keys: KeyConfig::new(),
+            keymap: Keymap::default(),
+            pending_keys: Vec::new(),
             time_remaining: work,
             total_duration: work,
             is_running: false,
+            run_started: None,
+            elapsed_before: Duration::ZERO,
+            last_tick: None,
+            suspend_gap: Duration::ZERO,
             break_count: 0,
             tasks: Vec::new(),
+            active_task: None,
+            auto_advance_task: true,
+            rollover_hour: None,
             task_state: ListState::default(),
-            input_buffer: String::new(),
+            task_filter: String::new(),
+            undo: Vec::new(),
+            redo: Vec::new(),
+            input: LineEditor::default(),
+            session_started_at: None,
+            history: Vec::new(),
+            archive: Vec::new(),
+            archive_state: ListState::default(),
+            stats_range: StatsRange::Week,
+            config_path: None,
+            data_path: None,
+            config_dirty: false,
+            config_broken: false,
+            notice: None,
+            confirm_overwrite: None,
+            config_errors: Vec::new(),
+            load_errors: Vec::new(),
+            config_stamp: None,
+            data_stamp: None,
+            saved_tasks: Vec::new(),
+            saved_active_task: None,
+            unsaved_since: None,
+            remote: None,
+            remote_snapshots: None,
             should_quit: false
         }
     }
 
     pub fn tick(&mut self) {
-        if self.is_running && self.time_remaining.as_secs() > 0 {
-            self.time_remaining -= Duration::from_secs(1);
-        } else if self.is_running && self.time_remaining.as_secs() == 0 {
-            let focus_msg = [
-                "I'm tired, boss...",
-                "Congrats! You're him 🗿",
-                "Stand up. Touch grass.",
-                "Mission Passed! Respect+",
-            ];
-            let break_msg = [
-                "Ah shit, here we go again.",
-                "Wake up, Samurai. We have code to burn.",
-                "Lock back in.",
-                "Ref! Do Something! The break's over!",
-            ];
-
-            // Use the remaining duration/break count as a seed for simple 'random' selection
-            let idx = (self.break_count as usize) % 4;
-
-            let (title, msg) = match self.mode {
-                SessionMode::Work => ("Focus Block Complete", focus_msg[idx]),
-                _ => ("Break Over", break_msg[idx]),
-            };
-
-            self.send_notification(title, msg);
-            self.transition_next_session();
-            self.is_running = true;
+        self.detect_suspend();
+        self.time_remaining = ceil_secs(self.total_duration.saturating_sub(self.elapsed()));
+
+        // Hold the block open while the user decides what to do with a suspend gap
+        if self.is_running && self.time_remaining.is_zero() && self.suspend_gap.is_zero() {
+            self.complete_session();
+        }
+    }
+
+    pub fn complete_session(&mut self) {
+        let finished = self.mode;
+        self.finish_session(true);
+        self.pause_timer();
+        self.transition_next_session();
+
+        let (title, event) = match (finished, self.mode) {
+            (SessionMode::Work, SessionMode::LongBreak) => ("Focus Block Complete", MessageEvent::LongBreakStart),
+            (SessionMode::Work, _) => ("Focus Block Complete", MessageEvent::FocusComplete),
+            (SessionMode::ShortBreak, _) => ("Break Over", MessageEvent::ShortBreakOver),
+            (SessionMode::LongBreak, _) => ("Break Over", MessageEvent::LongBreakOver),
+        };
+        let ctx = MessageContext {
+            task: self.focus_task().map(|t| t.title.as_str()),
+            count: self.break_count,
+            next: self.mode.label(),
+            duration_mins: self.total_duration.as_secs() / 60,
+        };
+        let msg = self.messages.pick(event, self.message_rotation[event as usize], &ctx);
+        self.message_rotation[event as usize] += 1;
+        self.send_notification(title, &msg);
+
+        let auto_start = match self.mode {
+            SessionMode::Work => self.auto_start_focus,
+            _ => self.auto_start_breaks,
+        };
+        if auto_start { self.start_timer(); }
+    }
+
+    // Remaining time is always derived from the wall clock, so a slow frame or a
+    // missed tick never loses time; only the accumulated pause-free stretches count
+    pub fn elapsed(&self) -> Duration {
+        let running = self.run_started
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .unwrap_or_default();
+        self.elapsed_before + running
+    }
+
+    pub fn start_timer(&mut self) {
+        if self.run_started.is_none() {
+            self.run_started = Some(SystemTime::now());
+        }
+        self.is_running = true;
+        if self.session_started_at.is_none() {
+            self.session_started_at = Some(Local::now());
+            let event = if self.mode == SessionMode::Work { HookEvent::FocusStart } else { HookEvent::BreakStart };
+            self.run_hook(event, None);
+        }
+    }
+
+    pub fn pause_timer(&mut self) {
+        self.elapsed_before = self.elapsed();
+        self.run_started = None;
+        self.is_running = false;
+    }
+
+    // User-initiated start/pause; unlike the raw timer controls these fire the pause and resume hooks
+    pub fn resume(&mut self) {
+        if self.is_running { return; }
+        let resuming = self.session_started_at.is_some();
+        self.start_timer();
+        if resuming { self.run_hook(HookEvent::Resume, None); }
+    }
+
+    pub fn pause(&mut self) {
+        if !self.is_running { return; }
+        self.pause_timer();
+        self.run_hook(HookEvent::Pause, None);
+    }
+
+    pub fn toggle_timer(&mut self) {
+        if self.is_running { self.pause() } else { self.resume() }
+    }
+
+    // Monotonic time stops while the machine sleeps but wall time does not, so a
+    // gap between the two means we were suspended mid-block. A prompt or modal
+    // that is open keeps its place; the suspend prompt follows once it closes.
+    fn detect_suspend(&mut self) {
+        let now = (Instant::now(), SystemTime::now());
+        let Some((last_mono, last_wall)) = self.last_tick.replace(now) else { return };
+        let wall = now.1.duration_since(last_wall).unwrap_or_default();
+        let gap = wall.saturating_sub(now.0 - last_mono);
+
+        if self.is_running && gap >= SUSPEND_THRESHOLD {
+            self.suspend_gap += gap;
+        }
+        if !self.suspend_gap.is_zero() && self.input_mode == InputMode::Normal {
+            self.input_mode = InputMode::SuspendPrompt;
+        }
+    }
+
+    // Abandons the current block and switches to `mode` at its full length
+    pub fn set_mode(&mut self, mode: SessionMode) {
+        self.finish_session(false);
+        self.mode = mode;
+        self.reset_timer_to_mode();
+    }
+
+    pub fn set_duration(&mut self, mode: SessionMode, duration: Duration) {
+        match mode {
+            SessionMode::Work => self.work_time = duration,
+            SessionMode::ShortBreak => self.short_break_time = duration,
+            SessionMode::LongBreak => self.long_break_time = duration,
+        }
+        self.run_lengths[mode as usize] = None;
+        self.config_dirty = true;
+        if mode == self.mode {
+            self.finish_session(false);
+            self.reset_timer_to_mode();
+        }
+    }
+
+    // Changes a length for this run only, without saving it. Unlike set_duration
+    // it leaves a block that is already under way alone.
+    pub fn set_run_duration(&mut self, mode: SessionMode, duration: Duration) {
+        self.run_lengths[mode as usize] = Some(duration);
+        if mode == self.mode && !self.is_running && self.time_remaining == self.total_duration {
+            self.reset_timer_to_mode();
+        }
+    }
+
+    pub fn reset_session(&mut self) {
+        self.finish_session(false);
+        self.reset_timer_to_mode();
+    }
+
+    // Moves on to whatever would follow the current block, without counting it as done
+    pub fn skip_session(&mut self) {
+        self.finish_session(false);
+        self.transition_next_session();
+    }
+
+    // Drops the current block from the count (logged as abandoned) and starts it over paused
+    pub fn void_session(&mut self) {
+        self.finish_session(false);
+        self.pause_timer();
+        self.reset_timer_to_mode();
+    }
+
+    // Appends the current block to the history log; `completed` is false for
+    // blocks that were reset, skipped or voided before reaching zero
+    pub fn finish_session(&mut self, completed: bool) {
+        if self.session_started_at.is_none() { return; }
+        let event = if self.mode == SessionMode::Work { HookEvent::FocusEnd } else { HookEvent::BreakEnd };
+        self.run_hook(event, None);
+        self.record_session(completed, Local::now());
+    }
+
+    fn record_session(&mut self, completed: bool, ended_at: DateTime<Local>) {
+        let Some(started_at) = self.session_started_at.take() else { return };
+        let focus = self.focus_index();
+        if completed && self.mode == SessionMode::Work && let Some(i) = focus {
+            self.tasks[i].pomodoros += 1;
+        }
+        let record = SessionRecord {
+            started_at,
+            ended_at,
+            mode: self.mode,
+            planned_secs: self.total_duration.as_secs(),
+            actual_secs: self.elapsed().min(self.total_duration).as_secs(),
+            completed,
+            task: focus.map(|i| self.tasks[i].title.clone()),
+            project: focus.and_then(|i| tasks::project_of(&self.tasks, i)).map(str::to_string),
+        };
+        let _ = history::append(&record);
+    }
+
+    pub fn session_state(&self) -> SessionState {
+        let remaining = self.total_duration.saturating_sub(self.elapsed());
+        SessionState {
+            mode: self.mode,
+            break_count: self.break_count,
+            is_running: self.is_running,
+            remaining_secs: ceil_secs(remaining).as_secs(),
+            deadline: self.is_running.then(|| Local::now() + remaining),
+            started_at: self.session_started_at,
+        }
+    }
+
+    // Changes whenever the persisted session differs in more than the countdown itself
+    pub fn session_key(&self) -> (SessionMode, bool, u32, Duration, Option<DateTime<Local>>) {
+        (self.mode, self.is_running, self.break_count, self.total_duration, self.session_started_at)
+    }
+
+    // Expects durations to be loaded already, since the block length comes from the mode
+    pub fn restore_session(&mut self, state: SessionState) {
+        self.mode = state.mode;
+        self.break_count = state.break_count;
+        self.is_running = false;
+        self.reset_timer_to_mode();
+        self.session_started_at = state.started_at;
+
+        let remaining = match state.deadline {
+            Some(deadline) if state.is_running => (deadline - Local::now()).to_std().unwrap_or_default(),
+            _ => Duration::from_secs(state.remaining_secs),
+        };
+        self.elapsed_before = self.total_duration.saturating_sub(remaining);
+
+        match state.deadline {
+            // The block ran out while pomoru was closed: log it as finished at its
+            // deadline and line up the next one, waiting for the user to start it
+            Some(deadline) if state.is_running && remaining.is_zero() => {
+                self.record_session(true, deadline);
+                self.transition_next_session();
+            }
+            _ if state.is_running => self.start_timer(),
+            _ => {}
         }
+        self.time_remaining = ceil_secs(self.total_duration.saturating_sub(self.elapsed()));
+    }
+
+    pub fn focus_task(&self) -> Option<&Task> {
+        self.focus_index().map(|i| &self.tasks[i])
+    }
+
+    // The pinned task, or the first actionable one when nothing is pinned
+    pub fn focus_index(&self) -> Option<usize> {
+        let today = Local::now().date_naive();
+        self.active_task
+            .and_then(|id| self.tasks.iter().position(|t| t.id == id && !t.is_done))
+            .or_else(|| (0..self.tasks.len()).find(|&i| tasks::is_actionable(&self.tasks, i, today)))
+    }
+
+    pub fn set_active_task(&mut self, id: Option<u64>) {
+        self.active_task = id.filter(|id| self.tasks.iter().any(|t| t.id == *id));
+    }
+
+    // Gives new tasks an id, derives parents from their subtasks, stamps when
+    // tasks were finished and moves the pin along once its task is done or gone
+    pub fn settle_tasks(&mut self) {
+        tasks::normalize_depths(&mut self.tasks);
+        tasks::derive_parents(&mut self.tasks);
+
+        let now = Local::now();
+        for task in &mut self.tasks {
+            if task.is_done { task.completed_at.get_or_insert(now); } else { task.completed_at = None; }
+        }
+
+        let mut next_id = self.tasks.iter().map(|t| t.id).max().unwrap_or(0);
+        for task in self.tasks.iter_mut().filter(|t| t.id == 0) {
+            next_id += 1;
+            task.id = next_id;
+        }
+
+        let Some(id) = self.active_task else { return };
+        let Some(i) = self.tasks.iter().position(|t| t.id == id) else {
+            self.active_task = None;
+            return;
+        };
+        if !self.tasks[i].is_done { return; }
+
+        // With auto-advance the pin moves to the next undone task below, wrapping around
+        self.active_task = if self.auto_advance_task {
+            let today = Local::now().date_naive();
+            (i..self.tasks.len()).chain(0..i)
+                .find(|&j| tasks::is_actionable(&self.tasks, j, today))
+                .map(|j| self.tasks[j].id)
+        } else {
+            None
+        };
     }
 
     fn transition_next_session(&mut self) {
-        match self.mode {
+        self.mode = match self.mode {
             SessionMode::Work => {
                 self.break_count += 1;
-                if self.break_count % 3 == 0 {
-                    self.mode = SessionMode::LongBreak;
-                    self.time_remaining = self.long_break_time;
-                    self.total_duration = self.long_break_time;
-                } else {
-                    self.mode = SessionMode::ShortBreak;
-                    self.time_remaining = self.short_break_time;
-                    self.total_duration = self.short_break_time;
-                }
-            }
-            _ => {
-                self.mode = SessionMode::Work;
-                self.time_remaining = self.work_time;
-                self.total_duration = self.work_time;
+                if self.break_count.is_multiple_of(self.long_break_interval.max(1)) { SessionMode::LongBreak } else { SessionMode::ShortBreak }
             }
-        }
+            _ => SessionMode::Work,
+        };
+        self.reset_timer_to_mode();
     }
 
-    pub fn reset_timer_to_mode(&mut self) {
-        self.time_remaining = match self.mode {
+    pub fn mode_duration(&self, mode: SessionMode) -> Duration {
+        self.run_lengths[mode as usize].unwrap_or(match mode {
             SessionMode::Work => self.work_time,
             SessionMode::ShortBreak => self.short_break_time,
             SessionMode::LongBreak => self.long_break_time,
-        };
-        self.total_duration = self.time_remaining;
+        })
+    }
+
+    pub fn reset_timer_to_mode(&mut self) {
+        self.total_duration = self.mode_duration(self.mode);
+        self.time_remaining = self.total_duration;
+        self.elapsed_before = Duration::ZERO;
+        self.run_started = None;
+        self.suspend_gap = Duration::ZERO;
+        if self.is_running { self.start_timer(); }
     }
 
     pub fn send_notification(&self, title: &str, message: &str) {
@@ -135,3 +633,16 @@ impl Pomo {
             .show();
     }
 }
+
+fn default_long_break_interval() -> u32 { 3 }
+
+fn default_true() -> bool { true }
+
+fn default_theme() -> String { DEFAULT_THEME.to_string() }
+
+fn is_zero(n: &u32) -> bool { *n == 0 }
+
+fn ceil_secs(d: Duration) -> Duration {
+    let secs = d.as_secs() + u64::from(d.subsec_nanos() > 0);
+    Duration::from_secs(secs)
+}
@@ -0,0 +1,124 @@
+use crate::pomo::history::SessionRecord;
+use crate::pomo::state::SessionMode;
+use chrono::{Datelike, Days, NaiveDate};
+use std::{ cmp::Reverse, collections::BTreeMap };
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum StatsRange { Today, Week, Month, AllTime }
+
+impl StatsRange {
+    pub const ALL: [StatsRange; 4] = [StatsRange::Today, StatsRange::Week, StatsRange::Month, StatsRange::AllTime];
+
+    pub fn next(self) -> Self {
+        match self {
+            StatsRange::Today => StatsRange::Week,
+            StatsRange::Week => StatsRange::Month,
+            StatsRange::Month => StatsRange::AllTime,
+            StatsRange::AllTime => StatsRange::Today,
+        }
+    }
+
+    pub fn previous(self) -> Self {
+        match self {
+            StatsRange::Today => StatsRange::AllTime,
+            StatsRange::Week => StatsRange::Today,
+            StatsRange::Month => StatsRange::Week,
+            StatsRange::AllTime => StatsRange::Month,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatsRange::Today => "Today",
+            StatsRange::Week => "This Week",
+            StatsRange::Month => "This Month",
+            StatsRange::AllTime => "All Time",
+        }
+    }
+
+    fn first_day(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            StatsRange::Today => Some(today),
+            StatsRange::Week => today.checked_sub_days(Days::new(today.weekday().num_days_from_monday() as u64)),
+            StatsRange::Month => today.with_day(1),
+            StatsRange::AllTime => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DayStats {
+    pub date: NaiveDate,
+    pub focus_secs: u64,
+    pub pomodoros: u64,
+    pub breaks: u64
+}
+
+// Focus time attributed to a project through the task that was the focus
+pub struct ProjectStats {
+    pub project: Option<String>,
+    pub focus_secs: u64,
+    pub pomodoros: u64
+}
+
+pub struct Summary {
+    pub days: Vec<DayStats>,
+    pub projects: Vec<ProjectStats>,
+    pub focus_secs: u64,
+    pub pomodoros: u64,
+    pub breaks: u64
+}
+
+impl Summary {
+    // Share of completed focus blocks that were followed through with a finished break
+    pub fn break_compliance(&self) -> Option<u64> {
+        if self.pomodoros == 0 { return None; }
+        Some((self.breaks * 100 / self.pomodoros).min(100))
+    }
+}
+
+pub fn summarize(records: &[SessionRecord], range: StatsRange, today: NaiveDate) -> Summary {
+    let earliest = records.iter().map(|r| r.started_at.date_naive()).min().unwrap_or(today);
+    let first_day = range.first_day(today).unwrap_or(earliest).min(today);
+
+    // One bucket per calendar day so quiet days still show up as empty bars
+    let mut days: Vec<DayStats> = first_day.iter_days()
+        .take_while(|d| *d <= today)
+        .map(|date| DayStats { date, focus_secs: 0, pomodoros: 0, breaks: 0 })
+        .collect();
+
+    let mut projects: BTreeMap<Option<String>, ProjectStats> = BTreeMap::new();
+    for record in records {
+        let date = record.started_at.date_naive();
+        if date < first_day || date > today { continue; }
+        let day = &mut days[(date - first_day).num_days() as usize];
+        match record.mode {
+            SessionMode::Work => {
+                day.focus_secs += record.actual_secs;
+                if record.completed { day.pomodoros += 1; }
+
+                let project = projects.entry(record.project.clone()).or_insert_with(|| ProjectStats {
+                    project: record.project.clone(), focus_secs: 0, pomodoros: 0
+                });
+                project.focus_secs += record.actual_secs;
+                if record.completed { project.pomodoros += 1; }
+            }
+            _ => if record.completed { day.breaks += 1; }
+        }
+    }
+    let mut projects: Vec<ProjectStats> = projects.into_values().collect();
+    projects.sort_by_key(|p| Reverse(p.focus_secs));
+
+    Summary {
+        focus_secs: days.iter().map(|d| d.focus_secs).sum(),
+        pomodoros: days.iter().map(|d| d.pomodoros).sum(),
+        breaks: days.iter().map(|d| d.breaks).sum(),
+        days,
+        projects,
+    }
+}
+
+pub fn format_hours(secs: u64) -> String {
+    let mins = secs / 60;
+    if mins < 60 { format!("{}m", mins) } else { format!("{}h {:02}m", mins / 60, mins % 60) }
+}
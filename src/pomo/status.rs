@@ -0,0 +1,54 @@
+use crate::pomo::daemon::Snapshot;
+use crate::pomo::state::SessionMode;
+use crate::pomo::ui::format_duration;
+use serde::Serialize;
+use std::time::Duration;
+
+pub const DEFAULT_TEMPLATE: &str = "{mode} {remaining}";
+
+// Output for waybar's `return-type = "json"` custom modules
+#[derive(Serialize)]
+struct WaybarStatus {
+    text: String,
+    tooltip: String,
+    alt: &'static str,
+    class: Vec<&'static str>,
+    percentage: u64
+}
+
+// Fills `{mode}`, `{remaining}`, `{state}`, `{task}` and `{count}` in a user template
+pub fn render(template: &str, snapshot: &Snapshot) -> String {
+    template
+        .replace("{mode}", snapshot.mode.label())
+        .replace("{remaining}", &format_duration(Duration::from_secs(snapshot.remaining_secs)))
+        .replace("{state}", if snapshot.is_running { "running" } else { "paused" })
+        .replace("{task}", snapshot.task.as_deref().unwrap_or(""))
+        .replace("{count}", &snapshot.break_count.to_string())
+}
+
+pub fn render_json(template: &str, snapshot: &Snapshot) -> String {
+    let alt = match snapshot.mode {
+        SessionMode::Work => "work",
+        SessionMode::ShortBreak => "short_break",
+        SessionMode::LongBreak => "long_break",
+    };
+    let state = if snapshot.is_running { "running" } else { "paused" };
+
+    let mut tooltip = format!(
+        "{} • {} left • {}",
+        snapshot.mode.label(), format_duration(Duration::from_secs(snapshot.remaining_secs)), state
+    );
+    if let Some(task) = &snapshot.task {
+        tooltip.push_str(&format!("\nCurrent Focus: {}", task));
+    }
+
+    let elapsed = snapshot.total_secs.saturating_sub(snapshot.remaining_secs);
+    let status = WaybarStatus {
+        text: render(template, snapshot),
+        tooltip,
+        alt,
+        class: vec![alt, state],
+        percentage: (elapsed * 100).checked_div(snapshot.total_secs).unwrap_or(0),
+    };
+    serde_json::to_string(&status).unwrap_or_default()
+}
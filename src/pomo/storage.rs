@@ -0,0 +1,109 @@
+use std::{ fs, io::Write, path::{Path, PathBuf}, process, sync::atomic::{AtomicUsize, Ordering}, time::{Duration, SystemTime} };
+
+// Older copies of the data file kept next to it, newest as `.1`
+const BACKUPS: usize = 5;
+// A new backup is taken on the first save after this long
+const BACKUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Tells apart the temporary files of writes made by one process
+static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+// Writes a temporary file next to `path` and renames it over, so a crash or
+// kill mid-write leaves either the old contents or the new, never half of each.
+// The TUI, the CLI and the daemon may write at once, so each write gets its own
+// temporary file.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = path.parent().ok_or("Invalid file path")?;
+    fs::create_dir_all(dir)?;
+
+    let tmp = with_suffix(path, &format!("{}.{}.tmp", process::id(), WRITES.fetch_add(1, Ordering::Relaxed)));
+    let written = fs::OpenOptions::new().write(true).create_new(true).open(&tmp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+
+    // Makes the rename itself durable
+    if let Ok(dir) = fs::File::open(dir) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+// Shifts `path.1` .. `path.4` up by one and copies `path` to `path.1`,
+// unless the newest backup is recent enough
+pub fn rotate_backups(path: &Path) -> std::io::Result<()> {
+    if !path.exists() { return Ok(()); }
+    let newest = backup_path(path, 1);
+    let age = fs::metadata(&newest).and_then(|m| m.modified()).ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok());
+    if age.is_some_and(|age| age < BACKUP_INTERVAL) { return Ok(()); }
+
+    for n in (1..BACKUPS).rev() {
+        let _ = fs::rename(backup_path(path, n), backup_path(path, n + 1));
+    }
+    fs::copy(path, newest)?;
+    Ok(())
+}
+
+// Keeps a file pomoru couldn't read as `path.bak`, so it is still there to fix
+// whatever happens to the original
+pub fn keep_copy(path: &Path) {
+    // An empty file has nothing to fix and would replace a copy that might
+    if fs::metadata(path).is_ok_and(|m| m.len() > 0) {
+        let _ = fs::copy(path, with_suffix(path, "bak"));
+    }
+}
+
+// `config.toml line 3, column 17: invalid type: ...` on one line, where the
+// parser's own message spans several
+pub fn describe_toml_error(path: &Path, content: &str, e: &toml::de::Error) -> String {
+    let name = file_name(path);
+    let message = e.message().trim();
+    match e.span() {
+        Some(span) => {
+            let before = content.get(..span.start).unwrap_or(content);
+            let line = before.matches('\n').count() + 1;
+            let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+            format!("{} line {}, column {}: {}", name, line, column, message)
+        }
+        None => format!("{}: {}", name, message),
+    }
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+pub fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Whether `path` was modified since `stamp` was taken. A file that is gone
+// counts as unchanged, so deleting it doesn't reset anything.
+pub fn changed_since(path: &Path, stamp: Option<SystemTime>) -> bool {
+    modified(path).is_some_and(|m| Some(m) != stamp)
+}
+
+// The file itself followed by its backups, newest first
+pub fn with_backups(path: &Path) -> Vec<PathBuf> {
+    std::iter::once(path.to_path_buf())
+        .chain((1..=BACKUPS).map(|n| backup_path(path, n)))
+        .collect()
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    with_suffix(path, &n.to_string())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
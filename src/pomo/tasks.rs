@@ -0,0 +1,650 @@
+use crate::pomo::archive;
+use crate::pomo::state::{Pomo, Task};
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate, TimeDelta, Weekday};
+use serde_json::Value;
+
+// How many task list edits `u` can step back through
+const UNDO_LIMIT: usize = 100;
+
+// The task list as it was before an edit, and which row was selected then
+#[derive(Clone)]
+pub struct UndoState {
+    pub tasks: Vec<Task>,
+    pub selected: Option<usize>
+}
+
+// Subtasks are stored flat: a task's children are the tasks right after it
+// with a greater depth, so the list reads top to bottom like an outline.
+
+// One past the last descendant of task `i`
+pub fn subtree_end(tasks: &[Task], i: usize) -> usize {
+    let depth = tasks[i].depth;
+    tasks[i + 1..].iter().position(|t| t.depth <= depth).map_or(tasks.len(), |n| i + 1 + n)
+}
+
+pub fn has_children(tasks: &[Task], i: usize) -> bool {
+    tasks.get(i + 1).is_some_and(|t| t.depth > tasks[i].depth)
+}
+
+// Indexes of the tasks that aren't hidden under a collapsed parent
+pub fn visible(tasks: &[Task]) -> Vec<usize> {
+    let mut rows = Vec::new();
+    let mut i = 0;
+    while i < tasks.len() {
+        rows.push(i);
+        i = if tasks[i].collapsed { subtree_end(tasks, i) } else { i + 1 };
+    }
+    rows
+}
+
+// Marks a task and everything under it
+pub fn set_done(tasks: &mut [Task], i: usize, done: bool) {
+    let end = subtree_end(tasks, i);
+    for task in &mut tasks[i..end] {
+        task.is_done = done;
+    }
+}
+
+// A parent is done exactly when all of its children are. Walking backwards
+// settles the deepest levels first.
+pub fn derive_parents(tasks: &mut [Task]) {
+    for i in (0..tasks.len()).rev() {
+        if !has_children(tasks, i) { continue; }
+        let depth = tasks[i].depth;
+        let end = subtree_end(tasks, i);
+        tasks[i].is_done = tasks[i + 1..end].iter().filter(|t| t.depth == depth + 1).all(|t| t.is_done);
+    }
+}
+
+// Fixes depths that skip a level, e.g. after the parent of a subtree was deleted
+pub fn normalize_depths(tasks: &mut [Task]) {
+    let mut max = 0;
+    for task in tasks.iter_mut() {
+        task.depth = task.depth.min(max);
+        max = task.depth + 1;
+    }
+}
+
+// A subtask without a project of its own belongs to its parent's
+pub fn project_of(tasks: &[Task], i: usize) -> Option<&str> {
+    let mut depth = tasks[i].depth + 1;
+    tasks[..=i].iter().rev()
+        .filter(|t| {
+            let ancestor = t.depth < depth;
+            if ancestor { depth = t.depth; }
+            ancestor
+        })
+        .find_map(|t| t.project.as_deref())
+}
+
+// Every word of the filter has to match: `+project`, `#tag`, or text in the title
+pub fn matches(tasks: &[Task], i: usize, filter: &str) -> bool {
+    let task = &tasks[i];
+    filter.split_whitespace().all(|word| {
+        let word = word.to_lowercase();
+        if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            project_of(tasks, i).is_some_and(|p| p.to_lowercase() == project)
+        } else if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+            task.tags.iter().any(|t| t.to_lowercase() == tag)
+        } else {
+            task.title.to_lowercase().contains(&word)
+        }
+    })
+}
+
+// Tasks that match the filter, along with their parents so they keep their place in the outline
+pub fn filtered(tasks: &[Task], filter: &str) -> Vec<usize> {
+    let mut keep = vec![false; tasks.len()];
+    for i in (0..tasks.len()).rev() {
+        keep[i] = matches(tasks, i, filter) || (i + 1..subtree_end(tasks, i)).any(|j| keep[j]);
+    }
+    (0..tasks.len()).filter(|i| keep[*i]).collect()
+}
+
+// Removes the finished tasks `pick` chooses, each with its subtasks. A subtask
+// that leaves without its parent takes the parent's project along.
+pub fn take_done(tasks: &mut Vec<Task>, pick: impl Fn(&Task) -> bool) -> Vec<Task> {
+    let mut taken = Vec::new();
+    let mut i = 0;
+    while i < tasks.len() {
+        if !tasks[i].is_done {
+            i += 1;
+            continue;
+        }
+        let end = subtree_end(tasks, i);
+        // A parent is finished last, so if it stays its subtasks stay too
+        if !pick(&tasks[i]) {
+            i = end;
+            continue;
+        }
+        let project = project_of(tasks, i).map(str::to_string);
+        let depth = tasks[i].depth;
+        let mut subtree: Vec<Task> = tasks.drain(i..end).collect();
+        subtree[0].project = project;
+        for task in &mut subtree {
+            task.depth -= depth;
+        }
+        taken.extend(subtree);
+    }
+    taken
+}
+
+// Latest archived first, keeping subtasks under their parent
+pub fn newest_first(tasks: Vec<Task>) -> Vec<Task> {
+    let mut groups: Vec<Vec<Task>> = Vec::new();
+    for task in tasks {
+        match groups.last_mut() {
+            Some(group) if task.depth > 0 => group.push(task),
+            _ => groups.push(vec![task]),
+        }
+    }
+    groups.into_iter().rev().flatten().collect()
+}
+
+// Something to work on now: not done, not split into subtasks, and not scheduled for later
+pub fn is_actionable(tasks: &[Task], i: usize, today: NaiveDate) -> bool {
+    !tasks[i].is_done && !has_children(tasks, i) && tasks[i].scheduled.is_none_or(|d| d <= today)
+}
+
+// The undone task with the earliest due date
+pub fn next_deadline(tasks: &[Task]) -> Option<&Task> {
+    tasks.iter().filter(|t| !t.is_done && t.due.is_some()).min_by_key(|t| t.due)
+}
+
+// `today`, `tomorrow`, a weekday (the next one, today included), `3d` / `2w` from
+// now, or an ISO date
+pub fn parse_date(s: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let s = s.to_lowercase();
+    match s.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        _ => {}
+    }
+    if let Ok(weekday) = s.parse::<Weekday>() {
+        let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+        return today.checked_add_days(Days::new(ahead as u64));
+    }
+    if let Some((n, unit)) = s.split_at_checked(s.len().saturating_sub(1))
+        && let Ok(n) = n.parse::<u64>() {
+        match unit {
+            "d" => return today.checked_add_days(Days::new(n)),
+            "w" => return today.checked_add_days(Days::new(n.checked_mul(7)?)),
+            _ => {}
+        }
+    }
+    NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok()
+}
+
+// Short relative label: "today", "tomorrow", "Fri", "Nov 3"
+pub fn date_label(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (date - today).num_days();
+    match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        2..=6 => date.format("%a").to_string(),
+        _ if date.year() == today.year() => date.format("%b %-d").to_string(),
+        _ => date.format("%Y-%m-%d").to_string(),
+    }
+}
+
+impl Task {
+    // Splits `+project`, `#tag`, `due:<date>` and `sched:<date>` words out of a
+    // typed line; the rest is the title
+    pub fn parse(line: &str) -> Self {
+        let mut task = Task::new(String::new());
+        task.set_entry(line);
+        task
+    }
+
+    pub fn set_entry(&mut self, line: &str) {
+        let today = Local::now().date_naive();
+        let mut words = Vec::new();
+        self.project = None;
+        self.tags.clear();
+        self.due = None;
+        self.scheduled = None;
+        for word in line.split_whitespace() {
+            if let Some(date) = word.strip_prefix("due:").and_then(|d| parse_date(d, today)) {
+                self.due = Some(date);
+            } else if let Some(date) = word.strip_prefix("sched:").and_then(|d| parse_date(d, today)) {
+                self.scheduled = Some(date);
+            } else if let Some(project) = word.strip_prefix('+').filter(|p| !p.is_empty()) {
+                self.project = Some(project.to_string());
+            } else if let Some(tag) = word.strip_prefix('#').filter(|t| !t.is_empty()) {
+                if !self.tags.iter().any(|t| t == tag) { self.tags.push(tag.to_string()); }
+            } else {
+                words.push(word);
+            }
+        }
+        self.title = words.join(" ");
+    }
+
+    // The line `set_entry` would turn back into this task
+    pub fn entry(&self) -> String {
+        let mut line = self.title.clone();
+        if let Some(project) = &self.project {
+            line.push_str(&format!(" +{}", project));
+        }
+        for tag in &self.tags {
+            line.push_str(&format!(" #{}", tag));
+        }
+        if let Some(due) = self.due {
+            line.push_str(&format!(" due:{}", due));
+        }
+        if let Some(scheduled) = self.scheduled {
+            line.push_str(&format!(" sched:{}", scheduled));
+        }
+        line
+    }
+}
+
+// Stable sort of each group of siblings, keeping subtrees attached to their parent
+pub fn sort(tasks: &mut Vec<Task>, today: NaiveDate) {
+    let mut groups = Vec::new();
+    let mut rest = std::mem::take(tasks);
+    while !rest.is_empty() {
+        let tail = rest.split_off(subtree_end(&rest, 0));
+        let mut children = rest.split_off(1);
+        sort(&mut children, today);
+        rest.extend(children);
+        groups.push(std::mem::replace(&mut rest, tail));
+    }
+    groups.sort_by_key(|group| group[0].sort_key(today));
+    tasks.extend(groups.into_iter().flatten());
+}
+
+// Three-way merge by id of two copies of a list that were edited separately
+// since `base`. Additions, removals and field edits from both sides are kept; a
+// field changed on both sides keeps `ours`, and a task removed on one side stays
+// if the other side edited it. The order follows whichever side reordered.
+pub fn merge(base: &[Task], ours: &[Task], theirs: &[Task]) -> Vec<Task> {
+    let find = |list: &'_ [Task], id: u64| list.iter().find(|t| t.id == id && id != 0).cloned();
+    // Two different tasks added under the same id on each side both stay; theirs gets a new one
+    let theirs: Vec<Task> = theirs.iter().map(|t| match find(ours, t.id) {
+        Some(o) if find(base, t.id).is_none() && o != *t => Task { id: 0, ..t.clone() },
+        _ => t.clone(),
+    }).collect();
+
+    let ours_reordered = shared_order(ours, base) != shared_order(base, ours);
+    let (first, second) = if ours_reordered { (ours, theirs.as_slice()) } else { (theirs.as_slice(), ours) };
+    let resolve = |task: &Task, in_first: bool| {
+        let other = if in_first { find(second, task.id) } else { None };
+        let (o, t) = if ours_reordered == in_first { (Some(task.clone()), other) } else { (other, Some(task.clone())) };
+        merge_task(find(base, task.id), o, t)
+    };
+
+    let mut merged: Vec<Task> = first.iter().filter_map(|t| resolve(t, true)).collect();
+    // Tasks only the other side has go in after the task they follow there
+    let mut at = 0;
+    for task in second {
+        if task.id != 0 && first.iter().any(|t| t.id == task.id) {
+            if let Some(i) = merged.iter().position(|t| t.id == task.id) { at = i + 1; }
+        } else if let Some(task) = resolve(task, false) {
+            merged.insert(at, task);
+            at += 1;
+        }
+    }
+    merged
+}
+
+// Ids of `list` that `other` also has, in `list`'s order
+fn shared_order(list: &[Task], other: &[Task]) -> Vec<u64> {
+    list.iter().map(|t| t.id).filter(|id| other.iter().any(|o| o.id == *id)).collect()
+}
+
+fn merge_task(base: Option<Task>, ours: Option<Task>, theirs: Option<Task>) -> Option<Task> {
+    match (base, ours, theirs) {
+        (Some(base), Some(ours), Some(theirs)) => Some(merge_fields(&base, ours, &theirs)),
+        (Some(base), Some(kept), None) | (Some(base), None, Some(kept)) => (kept != base).then_some(kept),
+        (None, Some(task), _) | (None, None, Some(task)) => Some(task),
+        (_, None, None) => None,
+    }
+}
+
+// Takes each field theirs changed that ours didn't
+fn merge_fields(base: &Task, ours: Task, theirs: &Task) -> Task {
+    let (Ok(Value::Object(base)), Ok(Value::Object(mut merged)), Ok(Value::Object(theirs))) =
+        (serde_json::to_value(base), serde_json::to_value(&ours), serde_json::to_value(theirs)) else { return ours };
+    let keys: Vec<String> = base.keys().chain(merged.keys()).chain(theirs.keys()).cloned().collect();
+    for key in keys {
+        if merged.get(&key) != base.get(&key) { continue; }
+        match theirs.get(&key) {
+            Some(value) => merged.insert(key, value.clone()),
+            None => merged.remove(&key),
+        };
+    }
+    serde_json::from_value(Value::Object(merged)).unwrap_or(ours)
+}
+
+impl Pomo {
+    // Indexes into `tasks` of the rows on the task screen. Folding is ignored
+    // while a filter is set so that matches inside folded parents still show.
+    pub fn task_rows(&self) -> Vec<usize> {
+        if self.task_filter.is_empty() { visible(&self.tasks) } else { filtered(&self.tasks, &self.task_filter) }
+    }
+
+    // Index into `tasks` of the selected row
+    pub fn selected_task(&self) -> Option<usize> {
+        let row = self.task_state.selected()?;
+        self.task_rows().get(row).copied()
+    }
+
+    pub fn select_task(&mut self, i: Option<usize>) {
+        // A task hidden under a collapsed parent selects that parent instead
+        let row = i.and_then(|i| self.task_rows().iter().rposition(|r| *r <= i));
+        self.task_state.select(row);
+    }
+
+    pub fn set_task_filter(&mut self, filter: &str) {
+        let selected = self.selected_task();
+        self.task_filter = filter.trim().to_string();
+        self.select_task(selected);
+    }
+
+    // Makes the selected subtree a child of the task above it
+    pub fn indent_task(&mut self) {
+        let Some(i) = self.selected_task() else { return };
+        if i == 0 || self.tasks[i].depth > self.tasks[i - 1].depth { return; }
+        let end = subtree_end(&self.tasks, i);
+        for task in &mut self.tasks[i..end] {
+            task.depth += 1;
+        }
+        self.expand_ancestors(i);
+        self.select_task(Some(i));
+    }
+
+    pub fn outdent_task(&mut self) {
+        let Some(i) = self.selected_task() else { return };
+        if self.tasks[i].depth == 0 { return; }
+        // Later siblings stay under the old parent, so move the subtree past them
+        let depth = self.tasks[i].depth;
+        let parent_end = self.tasks[i + 1..].iter().position(|t| t.depth < depth).map_or(self.tasks.len(), |n| i + 1 + n);
+        let end = subtree_end(&self.tasks, i);
+        for task in &mut self.tasks[i..end] {
+            task.depth -= 1;
+        }
+        self.tasks[i..parent_end].rotate_left(end - i);
+        self.select_task(Some(i + parent_end - end));
+    }
+
+    pub fn toggle_collapse(&mut self) {
+        let Some(i) = self.selected_task() else { return };
+        if has_children(&self.tasks, i) {
+            self.tasks[i].collapsed = !self.tasks[i].collapsed;
+        }
+    }
+
+    // Moves the selected subtree past its previous or next sibling
+    pub fn move_task(&mut self, offset: isize) {
+        let Some(i) = self.selected_task() else { return };
+        let depth = self.tasks[i].depth;
+        let end = subtree_end(&self.tasks, i);
+
+        if offset < 0 {
+            let Some(prev) = self.tasks[..i].iter().rposition(|t| t.depth <= depth) else { return };
+            if self.tasks[prev].depth < depth { return; }
+            self.tasks[prev..end].rotate_left(i - prev);
+            self.select_task(Some(prev));
+        } else {
+            if self.tasks.get(end).is_none_or(|t| t.depth < depth) { return; }
+            let next_end = subtree_end(&self.tasks, end);
+            self.tasks[i..next_end].rotate_left(end - i);
+            self.select_task(Some(i + next_end - end));
+        }
+    }
+
+    // Called after an edit with the list as it was before it; a new edit
+    // discards whatever could have been redone
+    pub fn push_undo(&mut self, state: UndoState) {
+        self.undo.push(state);
+        if self.undo.len() > UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) {
+        let Some(state) = self.undo.pop() else { return };
+        let current = self.undo_state();
+        self.redo.push(current);
+        self.restore_undo_state(state);
+    }
+
+    pub fn redo(&mut self) {
+        let Some(state) = self.redo.pop() else { return };
+        let current = self.undo_state();
+        self.undo.push(current);
+        self.restore_undo_state(state);
+    }
+
+    pub fn undo_state(&self) -> UndoState {
+        UndoState { tasks: self.tasks.clone(), selected: self.task_state.selected() }
+    }
+
+    fn restore_undo_state(&mut self, state: UndoState) {
+        self.tasks = state.tasks;
+        let rows = self.task_rows().len();
+        self.task_state.select(state.selected.zip(rows.checked_sub(1)).map(|(r, last)| r.min(last)));
+    }
+
+    // Moves every finished task to the archive
+    pub fn clear_done(&mut self) -> Result<(), String> {
+        self.archive_where(|_| true).map(|_| ())
+    }
+
+    // With `rollover_hour` set, tasks finished before that hour last came around
+    // are archived; returns whether any were. If the archive can't be written
+    // they stay in the list and the next tick tries again.
+    pub fn roll_over(&mut self) -> bool {
+        let Some(hour) = self.rollover_hour else { return false };
+        let day = |t: DateTime<Local>| (t - TimeDelta::hours(hour.into())).date_naive();
+        let today = day(Local::now());
+        self.archive_where(|t| t.completed_at.is_some_and(|c| day(c) < today)).unwrap_or(false)
+    }
+
+    // Tasks only leave the list once they are safely in the archive
+    fn archive_where(&mut self, pick: impl Fn(&Task) -> bool) -> Result<bool, String> {
+        let selected = self.selected_task().map(|i| self.tasks[i].id);
+        let row = self.task_state.selected();
+        let mut kept = self.tasks.clone();
+        let taken = take_done(&mut kept, pick);
+        if taken.is_empty() { return Ok(false); }
+        archive::append(&taken).map_err(|e| format!("Could not archive finished tasks: {}", e))?;
+        self.tasks = kept;
+        // Undoing past this would bring back tasks that are already in the archive
+        self.undo.clear();
+        self.redo.clear();
+
+        // Keep the selection on the same task, or on the row it was on if that task left
+        match self.tasks.iter().position(|t| Some(t.id) == selected) {
+            Some(i) => self.select_task(Some(i)),
+            None => {
+                let rows = self.task_rows().len();
+                self.task_state.select(row.zip(rows.checked_sub(1)).map(|(r, last)| r.min(last)));
+            }
+        }
+        Ok(true)
+    }
+
+    fn expand_ancestors(&mut self, i: usize) {
+        let mut depth = self.tasks[i].depth;
+        for task in self.tasks[..i].iter_mut().rev() {
+            if task.depth < depth {
+                task.collapsed = false;
+                depth = task.depth;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pomo::state::Priority;
+
+    fn task(id: u64, title: &str) -> Task {
+        Task { id, ..Task::new(title.to_string()) }
+    }
+
+    fn base() -> Vec<Task> {
+        vec![task(1, "one"), task(2, "two"), task(3, "three")]
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<(u64, &str)> {
+        tasks.iter().map(|t| (t.id, t.title.as_str())).collect()
+    }
+
+    #[test]
+    fn edits_to_different_fields_are_both_kept() {
+        let mut ours = base();
+        ours[0].is_done = true;
+        let mut theirs = base();
+        theirs[0].title = "one renamed".to_string();
+        theirs[0].priority = Some(Priority::High);
+
+        let merged = merge(&base(), &ours, &theirs);
+        assert_eq!(merged[0].title, "one renamed");
+        assert!(merged[0].is_done);
+        assert!(merged[0].priority == Some(Priority::High));
+        assert_eq!(merged.len(), 3);
+    }
+
+    #[test]
+    fn same_field_changed_on_both_sides_keeps_ours() {
+        let mut ours = base();
+        ours[1].title = "ours".to_string();
+        let mut theirs = base();
+        theirs[1].title = "theirs".to_string();
+        assert_eq!(merge(&base(), &ours, &theirs)[1].title, "ours");
+    }
+
+    #[test]
+    fn field_cleared_on_one_side_stays_cleared() {
+        let mut base = base();
+        base[0].priority = Some(Priority::Low);
+        let mut theirs = base.clone();
+        theirs[0].priority = None;
+        assert!(merge(&base, &base, &theirs)[0].priority.is_none());
+    }
+
+    #[test]
+    fn removal_wins_unless_the_other_side_edited_the_task() {
+        // Removed here, untouched there: gone
+        let mut ours = base();
+        ours.remove(1);
+        assert_eq!(titles(&merge(&base(), &ours, &base())), [(1, "one"), (3, "three")]);
+
+        // Removed there, edited here: stays with the edit
+        let mut ours = base();
+        ours[1].is_done = true;
+        let mut theirs = base();
+        theirs.remove(1);
+        let merged = merge(&base(), &ours, &theirs);
+        assert_eq!(titles(&merged), [(1, "one"), (2, "two"), (3, "three")]);
+        assert!(merged[1].is_done);
+
+        // Removed here, edited there: stays with the edit
+        let mut ours = base();
+        ours.remove(2);
+        let mut theirs = base();
+        theirs[2].title = "three edited".to_string();
+        assert_eq!(titles(&merge(&base(), &ours, &theirs)), [(1, "one"), (2, "two"), (3, "three edited")]);
+    }
+
+    #[test]
+    fn reorder_on_one_side_keeps_additions_from_the_other() {
+        // Ours moved three to the top; theirs added a task after one
+        let ours = vec![task(3, "three"), task(1, "one"), task(2, "two")];
+        let mut theirs = base();
+        theirs.insert(1, task(4, "four"));
+        assert_eq!(
+            titles(&merge(&base(), &ours, &theirs)),
+            [(3, "three"), (1, "one"), (4, "four"), (2, "two")]
+        );
+
+        // Theirs reversed the list; ours added a task after three, where it stays
+        let mut ours = base();
+        ours.push(task(4, "four"));
+        let theirs = vec![task(3, "three"), task(2, "two"), task(1, "one")];
+        assert_eq!(
+            titles(&merge(&base(), &ours, &theirs)),
+            [(3, "three"), (4, "four"), (2, "two"), (1, "one")]
+        );
+    }
+
+    #[test]
+    fn tasks_added_under_the_same_id_on_both_sides_are_both_kept() {
+        let mut ours = base();
+        ours.push(task(4, "ours"));
+        let mut theirs = base();
+        theirs.push(task(4, "theirs"));
+
+        let merged = merge(&base(), &ours, &theirs);
+        assert_eq!(merged.len(), 5);
+        assert!(merged.iter().any(|t| t.id == 4 && t.title == "ours"));
+        // Left for `settle_tasks` to give a fresh id
+        assert!(merged.iter().any(|t| t.id == 0 && t.title == "theirs"));
+    }
+
+    #[test]
+    fn same_task_added_on_both_sides_is_kept_once() {
+        let mut ours = base();
+        ours.push(task(4, "four"));
+        let theirs = ours.clone();
+        assert_eq!(merge(&base(), &ours, &theirs).len(), 4);
+    }
+
+    #[test]
+    fn tasks_without_ids_are_always_new() {
+        let mut theirs = base();
+        theirs.push(task(0, "added by hand"));
+        theirs.push(task(0, "added by hand"));
+        let merged = merge(&base(), &base(), &theirs);
+        assert_eq!(merged.iter().filter(|t| t.id == 0).count(), 2);
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    // A Friday
+    fn today() -> NaiveDate {
+        date(2026, 10, 16)
+    }
+
+    #[test]
+    fn weekday_includes_today() {
+        assert_eq!(parse_date("fri", today()), Some(today()));
+        assert_eq!(parse_date("Friday", today()), Some(today()));
+        assert_eq!(parse_date("thu", today()), Some(date(2026, 10, 22)));
+    }
+
+    #[test]
+    fn days_and_weeks_from_now() {
+        assert_eq!(parse_date("3d", today()), Some(date(2026, 10, 19)));
+        assert_eq!(parse_date("2w", today()), Some(date(2026, 10, 30)));
+        assert_eq!(parse_date("tomorrow", today()), Some(date(2026, 10, 17)));
+    }
+
+    #[test]
+    fn iso_date() {
+        assert_eq!(parse_date("2026-11-03", today()), Some(date(2026, 11, 3)));
+    }
+
+    #[test]
+    fn garbage_is_not_a_date() {
+        for word in ["", "soon", "w", "3x", "-2d", "2026-13-01"] {
+            assert_eq!(parse_date(word, today()), None, "{}", word);
+        }
+    }
+
+    #[test]
+    fn huge_offsets_are_not_a_date() {
+        assert_eq!(parse_date("9999999999999999999w", today()), None);
+        assert_eq!(parse_date("9999999999999999999d", today()), None);
+        let task = Task::parse("Plan due:9999999999999999999w");
+        assert!(task.due.is_none());
+        assert_eq!(task.title, "Plan due:9999999999999999999w");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::pomo::state::SessionMode;
+use crate::pomo::storage;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::{ fs, path::Path, str::FromStr };
+
+pub const DEFAULT_THEME: &str = "catppuccin-mocha";
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub focus: Color,
+    pub short_break: Color,
+    pub long_break: Color,
+    pub muted: Color,
+    pub surface: Color,
+    pub text: Color,
+    pub warning: Color
+}
+
+impl Theme {
+    pub fn accent(&self, mode: SessionMode) -> Color {
+        match mode {
+            SessionMode::Work => self.focus,
+            SessionMode::ShortBreak => self.short_break,
+            SessionMode::LongBreak => self.long_break,
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        builtin(DEFAULT_THEME).unwrap_or(TERMINAL)
+    }
+}
+
+const TERMINAL: Theme = Theme {
+    focus: Color::Magenta,
+    short_break: Color::Green,
+    long_break: Color::Blue,
+    muted: Color::DarkGray,
+    surface: Color::DarkGray,
+    text: Color::Reset,
+    warning: Color::Red,
+};
+
+const fn hex(rgb: u32) -> Color {
+    Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8)
+}
+
+pub fn builtin(name: &str) -> Option<Theme> {
+    let theme = match name {
+        "catppuccin-latte" => Theme {
+            focus: hex(0x7287fd), short_break: hex(0x40a02b), long_break: hex(0x1e66f5),
+            muted: hex(0x9ca0b0), surface: hex(0xccd0da), text: hex(0x4c4f69), warning: hex(0xd20f39),
+        },
+        "catppuccin-frappe" => Theme {
+            focus: hex(0xbabbf1), short_break: hex(0xa6d189), long_break: hex(0x8caaee),
+            muted: hex(0x737994), surface: hex(0x414559), text: hex(0xc6d0f5), warning: hex(0xe78284),
+        },
+        "catppuccin-macchiato" => Theme {
+            focus: hex(0xb7bdf8), short_break: hex(0xa6da95), long_break: hex(0x8aadf4),
+            muted: hex(0x6e738d), surface: hex(0x363a4f), text: hex(0xcad3f5), warning: hex(0xed8796),
+        },
+        "catppuccin-mocha" => Theme {
+            focus: hex(0xb4befe), short_break: hex(0xa6e3a1), long_break: hex(0x89b4fa),
+            muted: hex(0x6c7086), surface: hex(0x313244), text: hex(0xcdd6f4), warning: hex(0xf38ba8),
+        },
+        "gruvbox" | "gruvbox-dark" => Theme {
+            focus: hex(0xfe8019), short_break: hex(0xb8bb26), long_break: hex(0x83a598),
+            muted: hex(0x928374), surface: hex(0x3c3836), text: hex(0xebdbb2), warning: hex(0xfb4934),
+        },
+        "gruvbox-light" => Theme {
+            focus: hex(0xaf3a03), short_break: hex(0x79740e), long_break: hex(0x076678),
+            muted: hex(0x928374), surface: hex(0xebdbb2), text: hex(0x3c3836), warning: hex(0x9d0006),
+        },
+        "nord" => Theme {
+            focus: hex(0x88c0d0), short_break: hex(0xa3be8c), long_break: hex(0x81a1c1),
+            muted: hex(0x7b88a1), surface: hex(0x3b4252), text: hex(0xeceff4), warning: hex(0xbf616a),
+        },
+        "solarized-dark" => Theme {
+            focus: hex(0x268bd2), short_break: hex(0x859900), long_break: hex(0x2aa198),
+            muted: hex(0x586e75), surface: hex(0x073642), text: hex(0x93a1a1), warning: hex(0xdc322f),
+        },
+        "solarized-light" => Theme {
+            focus: hex(0x268bd2), short_break: hex(0x859900), long_break: hex(0x2aa198),
+            muted: hex(0x93a1a1), surface: hex(0xeee8d5), text: hex(0x586e75), warning: hex(0xdc322f),
+        },
+        "terminal" => TERMINAL,
+        _ => return None,
+    };
+    Some(theme)
+}
+
+// A user theme in `themes/<name>.toml`. Colours are `#rrggbb`, ANSI names or
+// 0-255 indexes; any that are left out come from the built-in `base` theme.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    focus: Option<String>,
+    short_break: Option<String>,
+    long_break: Option<String>,
+    muted: Option<String>,
+    surface: Option<String>,
+    text: Option<String>,
+    warning: Option<String>
+}
+
+// User themes shadow built-ins of the same name
+pub fn load(name: &str, themes_dir: Option<&Path>) -> Result<Theme, String> {
+    let path = themes_dir.map(|dir| dir.join(format!("{}.toml", name)));
+    let (path, content) = match path.map(|p| (fs::read_to_string(&p), p)) {
+        Some((Ok(content), path)) => (path, content),
+        _ => return builtin(name).ok_or_else(|| format!("Unknown theme \"{}\"", name)),
+    };
+
+    let file: ThemeFile = toml::from_str(&content).map_err(|e| storage::describe_toml_error(&path, &content, &e))?;
+    let base = match &file.base {
+        Some(base) => builtin(base).ok_or_else(|| format!("Theme \"{}\": unknown base \"{}\"", name, base))?,
+        None => Theme::default(),
+    };
+
+    let color = |value: &Option<String>, fallback: Color| match value {
+        Some(v) => Color::from_str(v).map_err(|_| format!("Theme \"{}\": invalid colour \"{}\"", name, v)),
+        None => Ok(fallback),
+    };
+    Ok(Theme {
+        focus: color(&file.focus, base.focus)?,
+        short_break: color(&file.short_break, base.short_break)?,
+        long_break: color(&file.long_break, base.long_break)?,
+        muted: color(&file.muted, base.muted)?,
+        surface: color(&file.surface, base.surface)?,
+        text: color(&file.text, base.text)?,
+        warning: color(&file.warning, base.warning)?,
+    })
+}
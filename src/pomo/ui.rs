@@ -1,10 +1,10 @@
 use ratatui::{prelude::*, widgets::*};
-use crate::pomo::state::{Pomo, AppScreen, SessionMode, InputMode};
-
-const MOCHA_LAVENDER: Color = Color::Rgb(180, 190, 254);
-const MOCHA_OVERLAY0: Color = Color::Rgb(108, 112, 134);
-const MOCHA_SURFACE0: Color = Color::Rgb(49, 50, 68);
-const MOCHA_TEXT: Color = Color::Rgb(205, 214, 244);
+use crate::pomo::state::{Pomo, AppScreen, SessionMode, InputMode, Priority};
+use crate::pomo::keymap::KeyContext;
+use crate::pomo::tasks;
+use crate::pomo::formats;
+use crate::pomo::stats::{self, StatsRange};
+use chrono::{Local, NaiveDate};
 
 pub fn render(f: &mut Frame, app: &mut Pomo) {
     let main_block = Block::default().style(Style::default().bg(Color::Reset));
@@ -32,30 +32,46 @@ pub fn render(f: &mut Frame, app: &mut Pomo) {
 
             render_timer_screen(f, app, timer_v_center[1]);
 
-            let footer = "tab session • t tasks • e edit time • space pause • r reset • q quit";
+            let footer = footer_hints(app, KeyContext::Timer, root_layout[1].width);
             f.render_widget(
                 Paragraph::new(footer)
                     .alignment(Alignment::Center)
-                    .style(Style::default().fg(MOCHA_OVERLAY0)),
+                    .style(Style::default().fg(app.theme.muted)),
                 root_layout[1]
             );
         }
         AppScreen::Tasks => {
             render_task_screen(f, app, root_layout[1]); 
         }
+        AppScreen::Stats => {
+            render_stats_screen(f, app, root_layout[1]);
+        }
+        AppScreen::Archive => {
+            render_archive_screen(f, app, root_layout[1]);
+        }
     }
 
-    if let InputMode::Insert | InputMode::Edit | InputMode::TimerEdit = app.input_mode {
+    if let InputMode::Insert | InputMode::InsertSubtask | InputMode::Edit | InputMode::Filter | InputMode::Import | InputMode::Export | InputMode::TimerEdit = app.input_mode {
         render_input_modal(f, app);
     }
+
+    if app.input_mode == InputMode::SuspendPrompt {
+        render_suspend_modal(f, app);
+    }
+
+    if !app.load_problems().is_empty() {
+        render_load_errors(f, app);
+    }
 }
 
 fn render_timer_screen(f: &mut Frame, app: &Pomo, area: Rect) {
+    let accent = app.theme.accent(app.mode);
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // Priority Text
-            Constraint::Length(4), // Spacer
+            Constraint::Length(1), // Next Deadline
+            Constraint::Length(3), // Spacer
             Constraint::Length(5), // ASCII Timer
             Constraint::Length(3), // Spacer
             Constraint::Length(1), // Session Dots
@@ -63,25 +79,60 @@ fn render_timer_screen(f: &mut Frame, app: &Pomo, area: Rect) {
         ])
         .split(area);
 
-    let priority_text = app.tasks.iter().find(|t| !t.is_done)
-        .map(|t| format!("Current Focus: {}", t.title))
-        .unwrap_or_else(|| "No Active Tasks".to_string());
+    let priority_text = match app.focus_task() {
+        Some(t) => {
+            let pin = if app.active_task == Some(t.id) { " 󰐃" } else { "" };
+            let progress = t.progress().map(|p| format!("  󰔟 {}", p)).unwrap_or_default();
+            // Past its estimate the count turns into a warning
+            let progress_color = if t.is_over_estimate() { app.theme.warning } else { app.theme.muted };
+            Line::from(vec![
+                Span::raw(format!("Current Focus: {}{}", t.title, pin)),
+                Span::styled(progress, Style::default().fg(progress_color)),
+            ])
+        }
+        None => Line::from("No Active Tasks"),
+    };
  
     f.render_widget(
         Paragraph::new(priority_text)
             .alignment(Alignment::Center)
-            .style(Style::default().fg(MOCHA_LAVENDER).bold()), 
+            .style(Style::default().fg(accent).bold()), 
         chunks[0]
     );
 
+    if let Some(t) = tasks::next_deadline(&app.tasks) {
+        let today = Local::now().date_naive();
+        f.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(format!("Next deadline: {}", t.title), Style::default().fg(app.theme.muted)),
+                due_span(app, t.due, today),
+            ])).alignment(Alignment::Center),
+            chunks[1]
+        );
+    }
+
     let time_str = format_duration(app.time_remaining);
     let big_text = format_monolithic_ascii(&time_str);
     f.render_widget(
-        Paragraph::new(big_text).alignment(Alignment::Center).style(Style::default().fg(MOCHA_LAVENDER)), 
-        chunks[2]
+        Paragraph::new(big_text).alignment(Alignment::Center).style(Style::default().fg(accent)), 
+        chunks[3]
     );
 
-    render_session_dots(f, app, chunks[4]);
+    render_session_dots(f, app, chunks[5]);
+}
+
+// Overdue in the warning colour, due today in the accent, anything later muted
+fn due_span(app: &Pomo, due: Option<NaiveDate>, today: NaiveDate) -> Span<'static> {
+    let Some(due) = due else { return Span::raw("") };
+    let color = if due < today {
+        app.theme.warning
+    } else if due == today {
+        app.theme.accent(app.mode)
+    } else {
+        app.theme.muted
+    };
+    let prefix = if due < today { "overdue" } else { "due" };
+    Span::styled(format!("  {} {}", prefix, tasks::date_label(due, today)), Style::default().fg(color))
 }
 
 // Fixed-width monolithic ASCII engine
@@ -111,49 +162,263 @@ fn format_monolithic_ascii(time: &str) -> Text<'static> {
 }
 
 fn render_session_dots(f: &mut Frame, app: &Pomo, area: Rect) {
-    let modes = [(SessionMode::Work, "Focus"), (SessionMode::ShortBreak, "Short Break"), (SessionMode::LongBreak, "Long Break")];
-    let spans = modes.iter().enumerate().map(|(i, (mode, label))| {
+    let accent = app.theme.accent(app.mode);
+    let modes = [SessionMode::Work, SessionMode::ShortBreak, SessionMode::LongBreak];
+    let spans = modes.iter().enumerate().flat_map(|(i, mode)| {
         let is_active = app.mode == *mode;
-        let color = if is_active { MOCHA_LAVENDER } else { MOCHA_OVERLAY0 };
-        let content = if is_active { format!("• {}", label) } else { label.to_string() };
+        let color = if is_active { accent } else { app.theme.muted };
+        let content = if is_active { format!("• {}", mode.label()) } else { mode.label().to_string() };
         let mut s = vec![Span::styled(content, Style::default().fg(color))];
         if i < modes.len() - 1 { s.push(Span::raw("     ")); }
         s
-    }).flatten().collect::<Vec<_>>();
+    }).collect::<Vec<_>>();
 
     f.render_widget(Paragraph::new(Line::from(spans)).alignment(Alignment::Center), area);
 }
 
 pub fn render_task_screen(f: &mut Frame, app: &mut Pomo, footer_area: Rect) {
+    let accent = app.theme.accent(app.mode);
     let area = centered_rect(60, 80, f.area());
 
-    let items: Vec<ListItem> = app.tasks.iter().map(|t| {
+    // Only make room for the markers once something has a priority
+    let marker_width = if app.tasks.iter().any(|t| t.priority.is_some()) { 4 } else { 0 };
+    let focus = app.focus_task().map(|t| t.id);
+    let today = Local::now().date_naive();
+    let items: Vec<ListItem> = app.task_rows().into_iter().map(|i| {
+        let t = &app.tasks[i];
         let symbol = if t.is_done { "󰄲" } else { "󰄱" };
-        ListItem::new(Text::from(format!(" {} {}", symbol, t.title)))
+        // Parents get a fold arrow; a folded one also counts its finished subtasks
+        let (fold, folded_count) = if !tasks::has_children(&app.tasks, i) {
+            (" ", String::new())
+        } else if t.collapsed {
+            let children = &app.tasks[i + 1..tasks::subtree_end(&app.tasks, i)];
+            let done = children.iter().filter(|c| c.is_done).count();
+            ("▸", format!("  {}/{}", done, children.len()))
+        } else {
+            ("▾", String::new())
+        };
+        let marker = t.priority.map(Priority::marker).unwrap_or_default();
+        let marker_style = match t.priority {
+            Some(Priority::High) => Style::default().fg(accent).bold(),
+            Some(Priority::Medium) => Style::default().fg(app.theme.text),
+            _ => Style::default().fg(app.theme.muted),
+        };
+        let labels = t.project.iter().map(|p| format!("+{}", p))
+            .chain(t.tags.iter().map(|tag| format!("#{}", tag)))
+            .collect::<Vec<_>>();
+        // Scheduled for a later day: still listed, but dimmed
+        let later = !t.is_done && t.scheduled.is_some_and(|d| d > today);
+        let title_style = if focus == Some(t.id) {
+            Style::default().fg(accent)
+        } else if later {
+            Style::default().fg(app.theme.muted)
+        } else {
+            Style::default()
+        };
+        ListItem::new(Line::from(vec![
+            Span::raw("  ".repeat(t.depth as usize)),
+            Span::styled(fold, Style::default().fg(app.theme.muted)),
+            Span::raw(format!("{} ", symbol)),
+            Span::styled(format!("{:<width$}", marker, width = marker_width), marker_style),
+            Span::styled(t.title.clone(), title_style),
+            Span::styled(if app.active_task == Some(t.id) { " 󰐃" } else { "" }, Style::default().fg(accent)),
+            Span::styled(folded_count, Style::default().fg(app.theme.muted)),
+            Span::styled(
+                if labels.is_empty() { String::new() } else { format!("  {}", labels.join(" ")) },
+                Style::default().fg(app.theme.muted),
+            ),
+            Span::styled(
+                t.progress().map(|p| format!("  󰔟 {}", p)).unwrap_or_default(),
+                Style::default().fg(if t.is_over_estimate() { app.theme.warning } else { app.theme.muted }),
+            ),
+            if t.is_done { Span::raw("") } else { due_span(app, t.due, today) },
+            Span::styled(
+                t.scheduled.filter(|_| later).map(|d| format!("  sched {}", tasks::date_label(d, today))).unwrap_or_default(),
+                Style::default().fg(app.theme.muted),
+            ),
+        ]))
     }).collect();
 
+    let title = if app.task_filter.is_empty() {
+        " Focus Priorities ".to_string()
+    } else {
+        format!(" Focus Priorities · {} ", app.task_filter)
+    };
     let list = List::new(items)
         .block(Block::default()
-            .title(" Focus Priorities ")
+            .title(title)
             .borders(Borders::ALL)
             .border_type(BorderType::Rounded)
             .padding(Padding::uniform(1)) 
-            .border_style(Style::default().fg(MOCHA_LAVENDER)))
-        .highlight_style(Style::default().bg(MOCHA_SURFACE0).fg(MOCHA_TEXT).bold())
+            .border_style(Style::default().fg(accent)))
+        .highlight_style(Style::default().bg(app.theme.surface).fg(app.theme.text).bold())
         .highlight_symbol(">> ");
 
     f.render_stateful_widget(list, area, &mut app.task_state);
 
-    let footer_text = "i insert • ⏎ toggle • e edit • d delete • t back";
+    let (footer_text, footer_color) = match &app.notice {
+        Some(Ok(message)) => (message.clone(), app.theme.text),
+        Some(Err(message)) => (message.clone(), app.theme.warning),
+        None => (footer_hints(app, KeyContext::Tasks, footer_area.width), app.theme.muted),
+    };
+    f.render_widget(
+        Paragraph::new(footer_text)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(footer_color)),
+        footer_area
+    );
+}
+
+pub fn render_archive_screen(f: &mut Frame, app: &mut Pomo, footer_area: Rect) {
+    let accent = app.theme.accent(app.mode);
+    let area = centered_rect(60, 80, f.area());
+
+    // Subtasks are indented under their parent, which carries the date
+    let items: Vec<ListItem> = app.archive.iter().map(|t| {
+        let date = match t.completed_at {
+            Some(at) if t.depth == 0 => at.format("%b %e").to_string(),
+            _ => String::new(),
+        };
+        let labels = t.project.iter().map(|p| format!("+{}", p))
+            .chain(t.tags.iter().map(|tag| format!("#{}", tag)))
+            .collect::<Vec<_>>();
+        ListItem::new(Line::from(vec![
+            Span::styled(format!("{:<8}", date), Style::default().fg(app.theme.muted)),
+            Span::raw("  ".repeat(t.depth as usize)),
+            Span::raw(format!("{} {}", if t.is_done { "󰄲" } else { "󰄱" }, t.title)),
+            Span::styled(
+                if labels.is_empty() { String::new() } else { format!("  {}", labels.join(" ")) },
+                Style::default().fg(app.theme.muted),
+            ),
+            Span::styled(
+                t.progress().map(|p| format!("  󰔟 {}", p)).unwrap_or_default(),
+                Style::default().fg(app.theme.muted),
+            ),
+        ]))
+    }).collect();
+
+    let title = format!(" Archive · {} ", app.archive.iter().filter(|t| t.depth == 0).count());
+    let list = List::new(items)
+        .block(Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .padding(Padding::uniform(1))
+            .border_style(Style::default().fg(accent)))
+        .highlight_style(Style::default().bg(app.theme.surface).fg(app.theme.text).bold())
+        .highlight_symbol(">> ");
+
+    f.render_stateful_widget(list, area, &mut app.archive_state);
+
+    let footer_text = footer_hints(app, KeyContext::Archive, footer_area.width);
+    f.render_widget(
+        Paragraph::new(footer_text)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(app.theme.muted)),
+        footer_area
+    );
+}
+
+pub fn render_stats_screen(f: &mut Frame, app: &Pomo, footer_area: Rect) {
+    let accent = app.theme.accent(app.mode);
+    let area = centered_rect(80, 80, f.area());
+    let summary = stats::summarize(&app.history, app.stats_range, Local::now().date_naive());
+
+    let block = Block::default()
+        .title(" Statistics ")
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::uniform(1))
+        .border_style(Style::default().fg(accent));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Range Tabs
+            Constraint::Length(1), // Spacer
+            Constraint::Length(1), // Totals
+            Constraint::Length(1), // Projects
+            Constraint::Length(1), // Spacer
+            Constraint::Min(6),    // Focus Minutes Chart
+            Constraint::Length(7), // Pomodoro Chart
+        ])
+        .split(inner);
+
+    let tabs = StatsRange::ALL.iter().enumerate().flat_map(|(i, range)| {
+        let is_active = app.stats_range == *range;
+        let style = if is_active { Style::default().fg(accent).bold() } else { Style::default().fg(app.theme.muted) };
+        let content = if is_active { format!("• {}", range.label()) } else { range.label().to_string() };
+        let mut s = vec![Span::styled(content, style)];
+        if i < StatsRange::ALL.len() - 1 { s.push(Span::raw("     ")); }
+        s
+    }).collect::<Vec<_>>();
+    f.render_widget(Paragraph::new(Line::from(tabs)).alignment(Alignment::Center), chunks[0]);
+
+    let compliance = summary.break_compliance()
+        .map(|pct| format!("{}%", pct))
+        .unwrap_or_else(|| "—".to_string());
+    let totals = format!(
+        "Focus {} • {} pomodoros • Breaks taken {}",
+        stats::format_hours(summary.focus_secs), summary.pomodoros, compliance
+    );
+    f.render_widget(
+        Paragraph::new(totals).alignment(Alignment::Center).style(Style::default().fg(app.theme.text)),
+        chunks[2]
+    );
+
+    // Untagged time only gets a mention once some of it is attributed to a project
+    if summary.projects.iter().any(|p| p.project.is_some()) {
+        let projects = summary.projects.iter()
+            .map(|p| format!("{} {}", p.project.as_deref().map_or("(none)".to_string(), |name| format!("+{}", name)), stats::format_hours(p.focus_secs)))
+            .collect::<Vec<_>>()
+            .join(" • ");
+        f.render_widget(
+            Paragraph::new(projects).alignment(Alignment::Center).style(Style::default().fg(app.theme.muted)),
+            chunks[3]
+        );
+    }
+
+    // Only the most recent days that fit are drawn when the range is long
+    let bar_width = 5;
+    let visible = (chunks[5].width / (bar_width + 1)).max(1) as usize;
+    let days = &summary.days[summary.days.len().saturating_sub(visible)..];
+
+    let label = |d: &stats::DayStats| {
+        let fmt = if days.len() <= 7 { "%a" } else { "%d" };
+        Line::from(d.date.format(fmt).to_string())
+    };
+    let focus_bars = days.iter()
+        .map(|d| Bar::default().value(d.focus_secs / 60).label(label(d)))
+        .collect::<Vec<_>>();
+    let pomodoro_bars = days.iter()
+        .map(|d| Bar::default().value(d.pomodoros).label(label(d)))
+        .collect::<Vec<_>>();
+
+    let chart = |title: &'static str, bars: &[Bar<'static>]| BarChart::default()
+        .block(Block::default().title(Span::styled(title, Style::default().fg(app.theme.muted))))
+        .data(BarGroup::default().bars(bars))
+        .bar_width(bar_width)
+        .bar_gap(1)
+        .bar_style(Style::default().fg(accent))
+        .value_style(Style::default().fg(app.theme.surface).bg(accent))
+        .label_style(Style::default().fg(app.theme.muted));
+
+    f.render_widget(chart("Focus minutes per day", &focus_bars), chunks[5]);
+    f.render_widget(chart("Pomodoros per day", &pomodoro_bars), chunks[6]);
+
+    let footer_text = footer_hints(app, KeyContext::Stats, footer_area.width);
     f.render_widget(
         Paragraph::new(footer_text)
             .alignment(Alignment::Center)
-            .style(Style::default().fg(MOCHA_OVERLAY0)),
+            .style(Style::default().fg(app.theme.muted)),
         footer_area
     );
 }
 
 pub fn render_input_modal(f: &mut Frame, app: &Pomo) {
+    let accent = app.theme.accent(app.mode);
     let (title, width) = match app.input_mode { 
         InputMode::TimerEdit => (" Set Minutes ", 30), 
         _ => (" Input ", 50), 
@@ -175,16 +440,21 @@ pub fn render_input_modal(f: &mut Frame, app: &Pomo) {
 
     let title_text = match app.input_mode {
         InputMode::Insert => " New Task ",
+        InputMode::InsertSubtask => " New Subtask ",
         InputMode::Edit => " Edit Task ",
+        InputMode::Filter => " Filter: +project #tag text ",
+        InputMode::Import => " Import from file (.txt, .md, .json) ",
+        InputMode::Export if app.confirm_overwrite.as_ref().is_some_and(|p| *p == formats::expand_home(app.input.text())) => " File exists, confirm again to replace it ",
+        InputMode::Export => " Export to file (.txt, .md, .json) ",
         InputMode::TimerEdit => " Set Minutes ",
         _ => title
     };
 
     let block = Block::default()
-        .title(Span::styled(title_text, Style::default().fg(MOCHA_LAVENDER).bold()))
+        .title(Span::styled(title_text, Style::default().fg(accent).bold()))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
-        .border_style(Style::default().fg(MOCHA_LAVENDER));
+        .border_style(Style::default().fg(accent));
 
     // Nested layout for perfect internal vertical centering
     let inner_area = block.inner(area);
@@ -198,27 +468,118 @@ pub fn render_input_modal(f: &mut Frame, app: &Pomo) {
         .split(inner_area);
 
     let horizontal_padding = 2;
-    let input_len = app.input_buffer.len() as u16;
     let max_width = vertical_chunks[1].width.saturating_sub(horizontal_padding * 2);
-    let scroll = input_len.saturating_sub(max_width);
+    // Columns, not bytes or chars, so wide characters keep the cursor in place
+    let (visible, cursor_col) = app.input.view(max_width as usize);
 
     f.render_widget(block, area);
 
     f.render_widget(
-        Paragraph::new(app.input_buffer.as_str())
-            .scroll((0, scroll))
+        Paragraph::new(visible)
             .block(Block::default().padding(Padding::horizontal(horizontal_padding)))
-            .style(Style::default().fg(MOCHA_TEXT).bold()), 
+            .style(Style::default().fg(app.theme.text).bold()), 
         vertical_chunks[1]
     );
 
     f.set_cursor_position((
-        vertical_chunks[1].x + horizontal_padding + input_len.min(max_width),
+        vertical_chunks[1].x + horizontal_padding + cursor_col as u16,
         vertical_chunks[1].y,
     ));
 }
 
+// Banner across the top of the screen for problems found at startup, until a key is pressed
+fn render_load_errors(f: &mut Frame, app: &Pomo) {
+    let terminal_area = f.area();
+    let mut lines: Vec<Line> = app.load_problems().into_iter()
+        .map(|e| Line::from(e).style(Style::default().fg(app.theme.text)))
+        .collect();
+    if let Some(note) = app.load_error_note() {
+        lines.push(Line::from(note).style(Style::default().fg(app.theme.muted)));
+    }
+
+    let width = terminal_area.width.saturating_sub(4);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    let rows: usize = lines.iter().map(|l| l.width().div_ceil(inner).max(1)).sum();
+    let area = Rect {
+        x: terminal_area.x + 2,
+        y: terminal_area.y,
+        width,
+        height: (rows as u16 + 2).min(terminal_area.height),
+    };
+
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(Span::styled(" Config problems ", Style::default().fg(app.theme.warning).bold()))
+        .title_bottom(Span::styled(" any key to dismiss ", Style::default().fg(app.theme.muted)))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(app.theme.warning));
+
+    f.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }).block(block), area);
+}
+
+pub fn render_suspend_modal(f: &mut Frame, app: &Pomo) {
+    let accent = app.theme.accent(app.mode);
+    let terminal_area = f.area();
+    let modal_width = 50.min(terminal_area.width.saturating_sub(4));
+    let modal_height = 7;
+
+    let area = Rect {
+        x: terminal_area.x + (terminal_area.width.saturating_sub(modal_width)) / 2,
+        y: terminal_area.y + (terminal_area.height.saturating_sub(modal_height)) / 2,
+        width: modal_width,
+        height: modal_height,
+    };
+
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(Span::styled(" Welcome Back ", Style::default().fg(accent).bold()))
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .border_style(Style::default().fg(accent));
+
+    let status = if app.time_remaining.is_zero() {
+        "The block ended while you were away.".to_string()
+    } else {
+        format!("{} left in this block.", format_duration(app.time_remaining))
+    };
+    let text = Text::from(vec![
+        Line::from(format!("System was suspended for {}.", format_duration(app.suspend_gap))),
+        Line::from(status),
+        Line::from(""),
+        Line::from(footer_hints(app, KeyContext::Suspend, area.width.saturating_sub(2))).style(Style::default().fg(app.theme.muted)),
+    ]);
+
+    f.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(app.theme.text))
+            .block(block),
+        area
+    );
+}
+
 // --- UTILITIES ---
+// Joins as many hints as fit in `width`. While a multi-key sequence is half
+// typed, that is shown in place of the hints.
+fn footer_hints(app: &Pomo, context: KeyContext, width: u16) -> String {
+    if !app.pending_keys.is_empty() {
+        let keys = app.pending_keys.iter().map(|k| k.label()).collect::<Vec<_>>().join(" ");
+        return format!("{} …", keys);
+    }
+
+    let mut footer = String::new();
+    for hint in app.keymap.hints(context) {
+        let sep = if footer.is_empty() { "" } else { " • " };
+        if footer.chars().count() + sep.len() + hint.chars().count() > width as usize { break; }
+        footer.push_str(sep);
+        footer.push_str(&hint);
+    }
+    footer
+}
+
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)